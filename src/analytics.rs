@@ -0,0 +1,1021 @@
+use std::collections::BTreeMap;
+
+use crate::book::{level_quantity, OrderBook, Side, Trade};
+
+/// Total quantity filled at each execution price across `trades`.
+pub fn fill_price_histogram(trades: &[Trade]) -> BTreeMap<i64, i64> {
+    let mut histogram = BTreeMap::new();
+    for trade in trades {
+        let filled = histogram.entry(trade.price).or_insert(0i64);
+        *filled = filled.saturating_add(trade.quantity);
+    }
+    histogram
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeStats {
+    pub min: i64,
+    pub max: i64,
+    pub mean: f64,
+    pub median: f64,
+}
+
+/// Carry, in basis points per funding period, implied by a book that is
+/// entirely one-sided by weight. See `OrderBook::implied_carry`.
+pub const MAX_IMPLIED_CARRY_BPS: f64 = 10.0;
+
+/// Outcome of a hypothetical sweep, from `OrderBook::estimate_fill`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillEstimate {
+    pub filled_quantity: i64,
+    /// Quantity left over once the opposite side runs dry.
+    pub unfilled_quantity: i64,
+    /// Volume-weighted fill price in cents, `None` if nothing would fill.
+    pub average_price: Option<f64>,
+}
+
+/// Resting orders laid out column by column; entry `i` of every column
+/// describes the same order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderColumns {
+    pub ids: Vec<u64>,
+    pub sides: Vec<Side>,
+    pub prices: Vec<i64>,
+    pub quantities: Vec<i64>,
+    pub timestamps: Vec<u64>,
+}
+
+impl OrderBook {
+    /// Room left to improve on the best `side` price while still resting
+    /// inside the spread: the spread minus one tick. `None` for a one-sided book.
+    pub fn improvement_opportunity(&self, side: Side) -> Option<i64> {
+        let (bid, _) = self.best_buy()?;
+        let (ask, _) = self.best_sell()?;
+        let ticks = &self.config().tick_table;
+        let room = match side {
+            Side::Buy => ask - (bid + ticks.tick_for(bid)),
+            Side::Sell => (ask - ticks.tick_for(ask)) - bid,
+        };
+        Some(room.max(0))
+    }
+
+    /// Change in (best bid, best ask) between the book state after sequence
+    /// `from_seq` and after `to_seq`. Requires `record_bbo_history`; `None` if
+    /// either side was empty at either point.
+    pub fn bbo_drift(&self, from_seq: u64, to_seq: u64) -> Option<(i64, i64)> {
+        let bbo_at = |seq: u64| {
+            self.bbo_history
+                .iter()
+                .rev()
+                .find(|&&(s, _, _)| s <= seq)
+                .map(|&(_, bid, ask)| (bid, ask))
+        };
+        let (from_bid, from_ask) = bbo_at(from_seq)?;
+        let (to_bid, to_ask) = bbo_at(to_seq)?;
+        Some((to_bid? - from_bid?, to_ask? - from_ask?))
+    }
+
+    /// Average realized spread over average quoted spread, from the BBO
+    /// history (needs `record_bbo_history`). A trade's realized spread is
+    /// `2 * (price - mid)` for a buy aggressor (`2 * (mid - price)` for a
+    /// sell), with `mid` taken `horizon` sequence numbers after the trade;
+    /// trades without that much history after them are left out. The quoted
+    /// spread is averaged over every two-sided BBO recorded.
+    ///
+    /// A ratio of 1 means the mid came back and makers kept the full quoted
+    /// spread. Below 1, prices moved on in the aggressors' direction and
+    /// makers kept only that fraction (adverse selection); below 0, they lost
+    /// money on the fills.
+    pub fn spread_efficiency(&self, horizon: u64) -> Option<f64> {
+        let last_seq = self.bbo_history.last()?.0;
+        let mid_at = |seq: u64| {
+            let &(_, bid, ask) = self.bbo_history.iter().rev().find(|&&(s, _, _)| s <= seq)?;
+            Some((bid? + ask?) as f64 / 2.0)
+        };
+        let realized: Vec<f64> = self
+            .trade_log
+            .iter()
+            .filter(|&&(seq, _, _)| seq + horizon <= last_seq)
+            .filter_map(|&(seq, price, aggressor)| {
+                let mid = mid_at(seq + horizon)?;
+                Some(match aggressor {
+                    Side::Buy => 2.0 * (price as f64 - mid),
+                    Side::Sell => 2.0 * (mid - price as f64),
+                })
+            })
+            .collect();
+        let quoted: Vec<f64> = self
+            .bbo_history
+            .iter()
+            .filter_map(|&(_, bid, ask)| Some((ask? - bid?) as f64))
+            .collect();
+        if realized.is_empty() || quoted.is_empty() {
+            return None;
+        }
+        let average = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        Some(average(&realized) / average(&quoted))
+    }
+
+    /// Heuristic fair value in cents:
+    ///
+    /// `fair = microprice + skew_weight * pressure * (ask - bid) / 2`
+    ///
+    /// where `microprice = (bid * ask_qty + ask * bid_qty) / (bid_qty + ask_qty)`
+    /// uses the touch sizes and `pressure = (B - A) / (B + A)` uses the total
+    /// bid (`B`) and ask (`A`) quantity over the top `levels`. A bid-heavy book
+    /// pushes the estimate up towards the ask, an ask-heavy one down towards
+    /// the bid. `skew_weight` comes from `BookConfig`.
+    pub fn skew_fair_value(&self, levels: usize) -> Option<f64> {
+        let (bid, bid_qty) = self.best_buy()?;
+        let (ask, ask_qty) = self.best_sell()?;
+
+        let microprice = (bid as f64 * ask_qty as f64 + ask as f64 * bid_qty as f64)
+            / (bid_qty as f64 + ask_qty as f64);
+
+        let bid_depth = self.depth_quantity(Side::Buy, levels) as f64;
+        let ask_depth = self.depth_quantity(Side::Sell, levels) as f64;
+        let pressure = (bid_depth - ask_depth) / (bid_depth + ask_depth);
+
+        let half_spread = (ask - bid) as f64 / 2.0;
+        Some(microprice + self.config().skew_weight * pressure * half_spread)
+    }
+
+    /// Midpoint of the best bid and ask in dollars (prices are stored in
+    /// cents, so the cent midpoint is divided by 100).
+    pub fn mid_price(&self) -> Option<f64> {
+        let (bid, _) = self.best_buy()?;
+        let (ask, _) = self.best_sell()?;
+        Some((bid as f64 + ask as f64) / 2.0 / 100.0)
+    }
+
+    /// Touch prices weighted by the opposite touch's size, in dollars:
+    /// `(bid * ask_qty + ask * bid_qty) / (bid_qty + ask_qty) / 100`. The
+    /// quantity scale cancels out of the ratio.
+    pub fn microprice(&self) -> Option<f64> {
+        let (bid, bid_qty) = self.best_buy()?;
+        let (ask, ask_qty) = self.best_sell()?;
+        let weighted = bid as f64 * ask_qty as f64 + ask as f64 * bid_qty as f64;
+        Some(weighted / (bid_qty as f64 + ask_qty as f64) / 100.0)
+    }
+
+    /// Top-of-book imbalance `(bid_qty - ask_qty) / (bid_qty + ask_qty)`, in
+    /// [-1, 1]. Dimensionless, so no scaling applies.
+    pub fn imbalance(&self) -> Option<f64> {
+        let (_, bid_qty) = self.best_buy()?;
+        let (_, ask_qty) = self.best_sell()?;
+        Some((bid_qty - ask_qty) as f64 / (bid_qty as f64 + ask_qty as f64))
+    }
+
+    /// Best bid quantity divided by best ask quantity.
+    pub fn touch_ratio(&self) -> Option<f64> {
+        let (_, bid_qty) = self.best_buy()?;
+        let (_, ask_qty) = self.best_sell()?;
+        Some(bid_qty as f64 / ask_qty as f64)
+    }
+
+    /// Resting quantity at the best bid and best ask, `None` for an empty side.
+    pub fn touch_liquidity(&self) -> (Option<i64>, Option<i64>) {
+        (self.best_buy().map(|(_, qty)| qty), self.best_sell().map(|(_, qty)| qty))
+    }
+
+    /// Average time (millis) a resting order spent on the book before being
+    /// filled, weighted by filled quantity. Each maker fill counts at its own
+    /// quantity, so a partially filled order contributes once per fill.
+    pub fn avg_time_in_book(&self) -> Option<f64> {
+        let (weighted, quantity) = self.time_in_book;
+        (quantity > 0).then(|| weighted as f64 / quantity as f64)
+    }
+
+    /// Share of a side's resting quantity that sits at its best price, in (0, 1].
+    pub fn touch_concentration(&self, side: Side) -> Option<f64> {
+        let mut levels = self.levels_from_touch(side);
+        let touch: i128 = levels.next()?.1.iter().map(|o| o.quantity as i128).sum();
+        let rest: i128 = levels.flat_map(|(_, orders)| orders.iter().map(|o| o.quantity as i128)).sum();
+        Some(touch as f64 / (touch + rest) as f64)
+    }
+
+    /// Mid price (cents) where each touch is weighted by `0.5^(age / half_life)`,
+    /// its age being `now` minus the newest `Order::created_at` at that level.
+    /// A stale quote pulls the mid less than a fresh one. `now` and
+    /// `half_life` are clock millis, so quiet periods age a quote too.
+    pub fn age_weighted_mid(&self, now: u64, half_life: u64) -> Option<f64> {
+        let touch = |side| {
+            self.levels_from_touch(side).next().map(|(&price, orders)| {
+                let newest = orders.iter().map(|o| o.created_at).max().unwrap_or(now);
+                let age = now.saturating_sub(newest) as f64;
+                (price as f64, 0.5f64.powf(age / half_life.max(1) as f64))
+            })
+        };
+        let (bid, bid_weight) = touch(Side::Buy)?;
+        let (ask, ask_weight) = touch(Side::Sell)?;
+        Some((bid * bid_weight + ask * ask_weight) / (bid_weight + ask_weight))
+    }
+
+    /// Expected impact in cents (average execution price versus the current
+    /// mid, positive = worse) of a `side` order for each of `sizes`, from a
+    /// read-only sweep. `None` where the book can't fill the size or there is
+    /// no mid.
+    pub fn slippage_curve(&self, side: Side, sizes: &[i64]) -> Vec<(i64, Option<i64>)> {
+        sizes.iter().map(|&size| (size, self.half_spread(side, size))).collect()
+    }
+
+    /// Effective half-spread in cents for executing `quantity` on `side`:
+    /// the average price of a read-only sweep versus the current mid,
+    /// positive = worse. `None` if the book can't fill the size or there is
+    /// no mid.
+    pub fn half_spread(&self, side: Side, quantity: i64) -> Option<i64> {
+        let ((bid, _), (ask, _)) = (self.best_buy()?, self.best_sell()?);
+        let mid = (bid + ask) as f64 / 2.0;
+        let (filled, notional) = self.sweep(side, quantity);
+        if quantity <= 0 || filled < quantity {
+            return None;
+        }
+        let avg_price = notional as f64 / filled as f64;
+        let impact = match side {
+            Side::Buy => avg_price - mid,
+            Side::Sell => mid - avg_price,
+        };
+        Some(impact.round() as i64)
+    }
+
+    /// Modelled slippage cost of working `total_qty` on `side` at
+    /// `participation` (0 to 1] of a market trading `volume_rate` quantity
+    /// units per period. The order goes out in slices of
+    /// `participation * volume_rate`, one per period; each slice sweeps the
+    /// current book (read-only) and pays its average price versus the mid.
+    /// The book is assumed to refill to its current shape between slices,
+    /// so smaller slices walk fewer levels. Returns the summed cost in
+    /// notional units (cents * quantity units); `None` for non-positive
+    /// inputs, a one-sided book, or a slice the book can't fill.
+    pub fn participation_slippage(&self, side: Side, total_qty: i64, participation: f64, volume_rate: f64) -> Option<f64> {
+        if total_qty <= 0 || participation <= 0.0 || volume_rate <= 0.0 {
+            return None;
+        }
+        let ((bid, _), (ask, _)) = (self.best_buy()?, self.best_sell()?);
+        let mid = (bid + ask) as f64 / 2.0;
+        let slice = ((participation.min(1.0) * volume_rate) as i64).clamp(1, total_qty);
+
+        let slice_cost = |quantity: i64| {
+            let (filled, notional) = self.sweep(side, quantity);
+            if filled < quantity {
+                return None;
+            }
+            let impact = match side {
+                Side::Buy => notional as f64 - mid * quantity as f64,
+                Side::Sell => mid * quantity as f64 - notional as f64,
+            };
+            Some(impact)
+        };
+
+        let full_slices = total_qty / slice;
+        let remainder = total_qty % slice;
+        let mut cost = slice_cost(slice)? * full_slices as f64;
+        if remainder > 0 {
+            cost += slice_cost(remainder)?;
+        }
+        Some(cost)
+    }
+
+    /// Expected cost of one round of quoting `size` on both sides, `spread`
+    /// cents wide, in notional units (cents * quantity units):
+    ///
+    /// `cost = 2 * fill_prob * size * (adverse_move - spread / 2)`
+    ///
+    /// Each side fills with probability `fill_prob`, earns half the spread
+    /// when it does, and then loses `adverse_move` cents as the price moves
+    /// against the filled quote. Negative means quoting is expected to pay.
+    pub fn quoting_cost(&self, size: i64, spread: i64, fill_prob: f64, adverse_move: i64) -> f64 {
+        let per_unit = adverse_move as f64 - spread as f64 / 2.0;
+        2.0 * fill_prob.clamp(0.0, 1.0) * size as f64 * per_unit
+    }
+
+    /// Number of resting orders on `side` per price bucket, as
+    /// `(bucket_start, count)` best bucket first. Buckets are `bucket_ticks`
+    /// ticks wide, using the tick at the touch, and aligned to multiples of
+    /// that width. Empty buckets are skipped.
+    pub fn order_count_by_bucket(&self, side: Side, bucket_ticks: i64) -> Vec<(i64, usize)> {
+        let Some((&touch, _)) = self.levels_from_touch(side).next() else {
+            return Vec::new();
+        };
+        let width = bucket_ticks * self.config().tick_table.tick_for(touch);
+        if width <= 0 {
+            return Vec::new();
+        }
+
+        let mut buckets: Vec<(i64, usize)> = Vec::new();
+        for (&price, orders) in self.levels_from_touch(side) {
+            let start = price.div_euclid(width) * width;
+            match buckets.last_mut() {
+                Some((last, count)) if *last == start => *count += orders.len(),
+                _ => buckets.push((start, orders.len())),
+            }
+        }
+        buckets
+    }
+
+    /// Price of each additional tranche for a `side` order sweeping the
+    /// opposite side: `(cumulative_quantity, price)` per level, best first,
+    /// where units up to `cumulative_quantity` fill at `price` or better.
+    pub fn marginal_price_ladder(&self, side: Side) -> Vec<(i64, i64)> {
+        let mut cumulative = 0i64;
+        self.levels_from_touch(side.opposite())
+            .map(|(&price, orders)| {
+                cumulative = cumulative.saturating_add(level_quantity(orders));
+                (cumulative, price)
+            })
+            .collect()
+    }
+
+    /// What a `side` order for `quantity` would fill right now, walking the
+    /// opposite side from the touch with no price limit. Read-only.
+    pub fn estimate_fill(&self, side: Side, quantity: i64) -> FillEstimate {
+        let (filled, notional) = self.sweep(side, quantity);
+        FillEstimate {
+            filled_quantity: filled,
+            unfilled_quantity: quantity - filled,
+            average_price: (filled > 0).then(|| notional as f64 / filled as f64),
+        }
+    }
+
+    /// Midpoint, in cents, of the average prices to buy and to sell `size`
+    /// by sweeping the book. Equals the touch mid while `size` fits at both
+    /// touches and moves toward the thinner side as it grows. `None` if
+    /// either side can't fill `size`.
+    pub fn size_adjusted_mid(&self, size: i64) -> Option<f64> {
+        let average_price = |side| {
+            let (filled, notional) = self.sweep(side, size);
+            (size > 0 && filled == size).then(|| notional as f64 / filled as f64)
+        };
+        Some((average_price(Side::Buy)? + average_price(Side::Sell)?) / 2.0)
+    }
+
+    /// Walks the side opposite `side` from the touch without modifying
+    /// anything. Returns (filled quantity, notional in cents * quantity units).
+    pub(crate) fn sweep(&self, side: Side, quantity: i64) -> (i64, i128) {
+        let mut filled = 0;
+        let mut notional = 0i128;
+        for (&price, orders) in self.levels_from_touch(side.opposite()) {
+            if filled >= quantity {
+                break;
+            }
+            let take = std::cmp::min(quantity - filled, level_quantity(orders));
+            filled += take;
+            notional += price as i128 * take as i128;
+        }
+        (filled, notional)
+    }
+
+    /// Cumulative depth imbalance `(B - A) / (B + A)` over the top `k` levels
+    /// of each side, for `k` in `1..=max_levels`. Empty for an empty book.
+    pub fn imbalance_profile(&self, max_levels: usize) -> Vec<(usize, f64)> {
+        (1..=max_levels)
+            .filter_map(|k| {
+                let bid = self.depth_quantity(Side::Buy, k) as f64;
+                let ask = self.depth_quantity(Side::Sell, k) as f64;
+                (bid + ask > 0.0).then(|| (k, (bid - ask) / (bid + ask)))
+            })
+            .collect()
+    }
+
+    /// Crude funding signal from book skew, in basis points per period.
+    /// Level quantities over the top `levels` are weighted by `1 / rank`
+    /// (touch = 1), the weighted imbalance `(B - A) / (B + A)` is taken and
+    /// scaled linearly so a fully bid-side book maps to
+    /// `+MAX_IMPLIED_CARRY_BPS`: positive means longs pay. `None` unless
+    /// both sides have orders.
+    pub fn implied_carry(&self, levels: usize) -> Option<f64> {
+        let weighted_depth = |side| -> f64 {
+            self.levels_from_touch(side)
+                .take(levels)
+                .enumerate()
+                .map(|(rank, (_, orders))| {
+                    level_quantity(orders) as f64 / (rank + 1) as f64
+                })
+                .sum()
+        };
+        let bid = weighted_depth(Side::Buy);
+        let ask = weighted_depth(Side::Sell);
+        if bid == 0.0 || ask == 0.0 {
+            return None;
+        }
+        Some((bid - ask) / (bid + ask) * MAX_IMPLIED_CARRY_BPS)
+    }
+
+    /// (bid, ask) notional over the top `levels` of each side: the sum of
+    /// price * quantity in cents times 0.0001 units.
+    pub fn quoted_value(&self, levels: usize) -> (i128, i128) {
+        let side_value = |side| {
+            self.levels_from_touch(side)
+                .take(levels)
+                .flat_map(|(&price, orders)| orders.iter().map(move |o| price as i128 * o.quantity as i128))
+                .sum()
+        };
+        (side_value(Side::Buy), side_value(Side::Sell))
+    }
+
+    fn depth_quantity(&self, side: Side, levels: usize) -> i64 {
+        self.levels_from_touch(side)
+            .take(levels)
+            .fold(0i64, |total, (_, orders)| total.saturating_add(level_quantity(orders)))
+    }
+
+    /// Heuristic expected execution price, in cents, for resting order `id`
+    /// if `recent_volume` of opposite-side flow trades against its side.
+    /// Flow fills better-priced levels and the orders ahead in the queue
+    /// first; whatever reaches the order fills at its price, and the rest is
+    /// assumed to be completed by crossing to the opposite touch. The result
+    /// is the blend of the two by quantity. Not a fill model to rely on.
+    /// `None` if the order isn't resting or the opposite side is empty.
+    pub fn expected_execution_price(&self, id: u64, recent_volume: i64) -> Option<f64> {
+        let order = self.get_order(id)?;
+        let (opposite_price, _) = match order.side {
+            Side::Buy => self.best_sell()?,
+            Side::Sell => self.best_buy()?,
+        };
+
+        let mut ahead = 0i64;
+        for (&price, orders) in self.levels_from_touch(order.side) {
+            if price == order.price {
+                let queued = orders.iter().take_while(|o| o.id != id);
+                ahead = queued.fold(ahead, |total, o| total.saturating_add(o.quantity));
+                break;
+            }
+            ahead = ahead.saturating_add(level_quantity(orders));
+        }
+
+        let passive = (recent_volume - ahead).clamp(0, order.quantity) as f64;
+        let fill_fraction = passive / order.quantity as f64;
+        Some(fill_fraction * order.price as f64 + (1.0 - fill_fraction) * opposite_price as f64)
+    }
+
+    /// Orders added to plus orders cancelled from `side` per second, over the
+    /// `window` millis ending at clock time `now` (unix millis). Adds count
+    /// orders that rested; cancels count `cancel_order` and `cancel_replace`.
+    pub fn churn_rate(&self, side: Side, window: u64, now: u64) -> f64 {
+        if window == 0 {
+            return 0.0;
+        }
+        let start = now.saturating_sub(window);
+        let count = self
+            .churn_log
+            .iter()
+            .filter(|&&(at, s)| s == side && at > start && at <= now)
+            .count();
+        count as f64 * 1000.0 / window as f64
+    }
+
+    /// Every resting order in columnar form: bids then asks, each in
+    /// ascending price order and queue order within a level.
+    pub fn to_columns(&self) -> OrderColumns {
+        let mut columns = OrderColumns::default();
+        for side in [Side::Buy, Side::Sell] {
+            for order in self.levels(side).values().flatten() {
+                columns.ids.push(order.id);
+                columns.sides.push(order.side);
+                columns.prices.push(order.price);
+                columns.quantities.push(order.quantity);
+                columns.timestamps.push(order.timestamp);
+            }
+        }
+        columns
+    }
+
+    /// Skewness of a side's resting quantity over distance from the touch
+    /// (cents): each level's distance weighted by its quantity, then
+    /// `m3 / m2^1.5` of that distribution. Positive means a long thin tail
+    /// away from the touch. `None` for an empty side or a single level.
+    pub fn quantity_skew(&self, side: Side) -> Option<f64> {
+        let levels: Vec<(i64, f64)> = self
+            .levels_from_touch(side)
+            .map(|(&price, orders)| (price, level_quantity(orders) as f64))
+            .collect();
+        let &(touch, _) = levels.first()?;
+        let total: f64 = levels.iter().map(|&(_, qty)| qty).sum();
+        let distance = |price: i64| (price - touch).abs() as f64;
+
+        let mean = levels.iter().map(|&(price, qty)| distance(price) * qty).sum::<f64>() / total;
+        let moment = |k: i32| {
+            levels.iter().map(|&(price, qty)| (distance(price) - mean).powi(k) * qty).sum::<f64>() / total
+        };
+        let variance = moment(2);
+        (variance > 0.0).then(|| moment(3) / variance.powf(1.5))
+    }
+
+    /// Min, max, mean and median resting order size on one side.
+    pub fn size_stats(&self, side: Side) -> Option<SizeStats> {
+        let mut sizes: Vec<i64> = self
+            .levels(side)
+            .values()
+            .flat_map(|orders| orders.iter().map(|o| o.quantity))
+            .collect();
+        if sizes.is_empty() {
+            return None;
+        }
+        sizes.sort_unstable();
+
+        let n = sizes.len();
+        let mean = sizes.iter().map(|&size| size as i128).sum::<i128>() as f64 / n as f64;
+        let median = if n % 2 == 1 {
+            sizes[n / 2] as f64
+        } else {
+            (sizes[n / 2 - 1] as f64 + sizes[n / 2] as f64) / 2.0
+        };
+
+        Some(SizeStats {
+            min: sizes[0],
+            max: sizes[n - 1],
+            mean,
+            median,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::BookConfig;
+
+    #[test]
+    fn test_improvement_opportunity() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert_eq!(book.improvement_opportunity(Side::Buy), None);
+
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        assert_eq!(book.improvement_opportunity(Side::Buy), None);
+
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        // $10.00 spread leaves 999 one-cent ticks to improve into
+        assert_eq!(book.improvement_opportunity(Side::Buy), Some(999));
+        assert_eq!(book.improvement_opportunity(Side::Sell), Some(999));
+    }
+
+    #[test]
+    fn test_touch_liquidity() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 5000, 2).unwrap();
+        book.place_order(Side::Buy, 99000, 40000, 3).unwrap();
+        assert_eq!(book.touch_liquidity(), (Some(15000), None));
+
+        book.place_order(Side::Sell, 100500, 20000, 4).unwrap();
+        assert_eq!(book.touch_liquidity(), (Some(15000), Some(20000)));
+    }
+
+    #[test]
+    fn test_avg_time_in_book() {
+        let clock = crate::clock::MockClock::new(0);
+        let mut book = OrderBook::with_clock("Valhalla/USD".to_string(), std::sync::Arc::new(clock.clone()));
+        assert_eq!(book.avg_time_in_book(), None);
+
+        book.place_order(Side::Sell, 100000, 10000, 1).unwrap();
+        clock.set(1_000);
+        book.place_order(Side::Sell, 100500, 30000, 2).unwrap();
+        clock.set(5_000);
+        book.place_order(Side::Buy, 100500, 40000, 3).unwrap();
+
+        // (1 x 5000ms + 3 x 4000ms) / 4
+        assert_eq!(book.avg_time_in_book(), Some(4250.0));
+    }
+
+    #[test]
+    fn test_top_of_book_stats() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 30000, 1).unwrap();
+        assert_eq!((book.mid_price(), book.microprice(), book.imbalance()), (None, None, None));
+
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        assert_eq!(book.mid_price(), Some(1000.0));
+        // ($995 x 1 + $1005 x 3) / 4
+        assert_eq!(book.microprice(), Some(1002.5));
+        assert_eq!(book.imbalance(), Some(0.5));
+    }
+
+    #[test]
+    fn test_quantity_skew() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert_eq!(book.quantity_skew(Side::Sell), None);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        assert_eq!(book.quantity_skew(Side::Sell), None);
+
+        // Bulk near the touch with one far level: long tail outward
+        book.place_order(Side::Sell, 100600, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 100700, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 102500, 5000, 4).unwrap();
+        assert!(book.quantity_skew(Side::Sell).unwrap() > 1.0);
+
+        book.place_order(Side::Buy, 99500, 10000, 5).unwrap();
+        book.place_order(Side::Buy, 99400, 20000, 6).unwrap();
+        book.place_order(Side::Buy, 99300, 10000, 7).unwrap();
+        assert!(book.quantity_skew(Side::Buy).unwrap().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_spread_efficiency() {
+        let config = BookConfig { record_bbo_history: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 20000, 2).unwrap();
+        book.place_order(Side::Buy, 100500, 10000, 3).unwrap();
+        assert_eq!(book.spread_efficiency(1), None);
+
+        // Bid moves up to $997: mid $1001 one order after the buy at $1005
+        book.place_order(Side::Buy, 99700, 10000, 4).unwrap();
+        // Realized 2 x $4 = 800, quoted (1000 + 1000 + 800) / 3
+        let ratio = book.spread_efficiency(1).unwrap();
+        assert!((ratio - 800.0 / (2800.0 / 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_size_adjusted_mid() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 50000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 103500, 50000, 4).unwrap();
+
+        assert_eq!(book.size_adjusted_mid(1), Some(100000.0));
+        assert_eq!(book.size_adjusted_mid(10000), Some(100000.0));
+        // Buy 3.0 averages $1025.00, sell 3.0 averages $991.67
+        let mid = book.size_adjusted_mid(30000).unwrap();
+        assert!((mid - (102500.0 + 99166.0 + 2.0 / 3.0) / 2.0).abs() < 1e-6);
+        assert_eq!(book.size_adjusted_mid(70000), None);
+    }
+
+    #[test]
+    fn test_order_count_by_bucket() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert!(book.order_count_by_bucket(Side::Buy, 100).is_empty());
+        let bids = [(99950, 1), (99950, 2), (99900, 3), (99850, 4), (99650, 5), (99610, 6)];
+        for (price, id) in bids {
+            book.place_order(Side::Buy, price, 10000, id).unwrap();
+        }
+        book.place_order(Side::Sell, 100050, 10000, 7).unwrap();
+        book.place_order(Side::Sell, 100210, 10000, 8).unwrap();
+
+        // $1 buckets
+        assert_eq!(book.order_count_by_bucket(Side::Buy, 100), vec![(99900, 3), (99800, 1), (99600, 2)]);
+        assert_eq!(book.order_count_by_bucket(Side::Sell, 100), vec![(100000, 1), (100200, 1)]);
+    }
+
+    #[test]
+    fn test_participation_slippage() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 102000, 20000, 4).unwrap();
+
+        // 10% of 100000/period: four 1.0 slices, each at the touch
+        let slow = book.participation_slippage(Side::Buy, 40000, 0.1, 100000.0).unwrap();
+        assert_eq!(slow, 4.0 * 500.0 * 10000.0);
+        // 40%: one slice sweeping three levels
+        let fast = book.participation_slippage(Side::Buy, 40000, 0.4, 100000.0).unwrap();
+        assert_eq!(fast, (500.0 + 1000.0 + 2.0 * 2000.0) * 10000.0);
+        assert!(slow < fast);
+
+        assert_eq!(book.participation_slippage(Side::Buy, 50000, 0.5, 100000.0), None);
+        assert_eq!(book.participation_slippage(Side::Buy, 40000, 0.0, 100000.0), None);
+    }
+
+    #[test]
+    fn test_quoting_cost() {
+        let book = OrderBook::new("Valhalla/USD".to_string());
+        // 1.0 each side, $2 wide, 50% fill, $3 adverse move: 2 x 0.5 x 10000 x (300 - 100)
+        assert_eq!(book.quoting_cost(10000, 200, 0.5, 300), 2_000_000.0);
+        assert!(book.quoting_cost(10000, 200, 0.5, 400) > book.quoting_cost(10000, 200, 0.5, 300));
+        assert!(book.quoting_cost(10000, 400, 0.5, 300) < book.quoting_cost(10000, 200, 0.5, 300));
+        // Wide enough to cover the move: quoting pays
+        assert!(book.quoting_cost(10000, 800, 0.5, 300) < 0.0);
+    }
+
+    #[test]
+    fn test_fill_price_histogram() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 20000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 30000, 3).unwrap();
+        book.place_order(Side::Sell, 101500, 40000, 4).unwrap();
+
+        let trades = book.place_order(Side::Buy, 102000, 80000, 5).unwrap();
+        assert_eq!(trades.len(), 4);
+
+        let histogram = fill_price_histogram(&trades);
+        let expected: BTreeMap<i64, i64> = [(100500, 30000), (101000, 30000), (101500, 20000)]
+            .into_iter()
+            .collect();
+        assert_eq!(histogram, expected);
+        assert!(fill_price_histogram(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_size_stats() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert_eq!(book.size_stats(Side::Buy), None);
+
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 40000, 2).unwrap();
+        book.place_order(Side::Buy, 99000, 20000, 3).unwrap();
+        book.place_order(Side::Buy, 98500, 90000, 4).unwrap();
+        book.place_order(Side::Sell, 100500, 70000, 5).unwrap();
+
+        let stats = book.size_stats(Side::Buy).unwrap();
+        assert_eq!(stats.min, 10000);
+        assert_eq!(stats.max, 90000);
+        assert_eq!(stats.mean, 40000.0);
+        assert_eq!(stats.median, 30000.0);
+
+        let stats = book.size_stats(Side::Sell).unwrap();
+        assert_eq!((stats.min, stats.max), (70000, 70000));
+        assert_eq!(stats.median, 70000.0);
+    }
+
+    #[test]
+    fn test_bbo_drift() {
+        let config = BookConfig { record_bbo_history: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        let start = book.sequence();
+
+        // Bid steps up, ask is lifted and the next level is higher
+        book.place_order(Side::Sell, 101000, 10000, 3).unwrap();
+        book.place_order(Side::Buy, 99800, 10000, 4).unwrap();
+        book.place_order(Side::Buy, 100500, 10000, 5).unwrap();
+        let end = book.sequence();
+
+        assert_eq!(book.bbo_drift(start, end), Some((300, 500)));
+        assert_eq!(book.bbo_drift(start, start), Some((0, 0)));
+        // Before the ask existed the book was one-sided
+        assert_eq!(book.bbo_drift(1, end), None);
+    }
+
+    #[test]
+    fn test_skew_fair_value_leans_with_liquidity() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        assert_eq!(book.skew_fair_value(3), None);
+
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        // Balanced book sits on the mid
+        assert_eq!(book.skew_fair_value(3), Some(100000.0));
+
+        book.place_order(Side::Buy, 99500, 30000, 3).unwrap();
+        book.place_order(Side::Buy, 99000, 40000, 4).unwrap();
+        let bid_heavy = book.skew_fair_value(3).unwrap();
+        assert!(bid_heavy > 100000.0 && bid_heavy < 100500.0, "{}", bid_heavy);
+
+        book.place_order(Side::Sell, 100500, 90000, 5).unwrap();
+        book.place_order(Side::Sell, 101000, 90000, 6).unwrap();
+        let ask_heavy = book.skew_fair_value(3).unwrap();
+        assert!(ask_heavy < 100000.0 && ask_heavy > 99500.0, "{}", ask_heavy);
+    }
+
+    #[test]
+    fn test_touch_ratio() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 30000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 30000, 2).unwrap();
+        book.place_order(Side::Buy, 99000, 90000, 3).unwrap();
+        assert_eq!(book.touch_ratio(), None);
+
+        book.place_order(Side::Sell, 100500, 40000, 4).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 5).unwrap();
+        assert_eq!(book.touch_ratio(), Some(1.5));
+    }
+
+    #[test]
+    fn test_touch_concentration() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert_eq!(book.touch_concentration(Side::Sell), None);
+
+        book.place_order(Side::Sell, 100500, 80000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 5000, 3).unwrap();
+        book.place_order(Side::Sell, 101500, 5000, 4).unwrap();
+        assert_eq!(book.touch_concentration(Side::Sell), Some(0.9));
+
+        book.place_order(Side::Buy, 99500, 10000, 5).unwrap();
+        assert_eq!(book.touch_concentration(Side::Buy), Some(1.0));
+    }
+
+    #[test]
+    fn test_age_weighted_mid_discounts_stale_quote() {
+        let clock = crate::clock::MockClock::new(10_000);
+        let mut book = OrderBook::with_clock("Valhalla/USD".to_string(), std::sync::Arc::new(clock.clone()));
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        clock.set(10_001);
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+
+        // With a long half-life the 1ms age gap barely matters
+        let mid = book.age_weighted_mid(10_001, 1_000_000).unwrap();
+        assert!((mid - 100000.0).abs() < 0.01, "{}", mid);
+
+        // Refresh the ask 4s later; the bid is now 4s old however few
+        // events happened in between
+        clock.set(14_000);
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+
+        // Bid weight 0.5^(4000/2000) = 0.25 against 1.0 for the ask
+        let mid = book.age_weighted_mid(14_000, 2_000).unwrap();
+        assert_eq!(mid, (99500.0 * 0.25 + 100500.0) / 1.25);
+        assert!(mid > 100000.0);
+    }
+
+    #[test]
+    fn test_slippage_curve_is_monotonic() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 3).unwrap();
+        book.place_order(Side::Sell, 102000, 30000, 4).unwrap();
+
+        let sizes = [5000, 10000, 20000, 40000, 60000, 60001];
+        let curve = book.slippage_curve(Side::Buy, &sizes);
+        assert_eq!(curve.len(), sizes.len());
+        // Filling within the touch costs half the spread
+        assert_eq!(curve[0], (5000, Some(500)));
+        assert_eq!(curve[5], (60001, None));
+
+        let impacts: Vec<i64> = curve.iter().filter_map(|&(_, impact)| impact).collect();
+        assert_eq!(impacts.len(), 5);
+        assert!(impacts.windows(2).all(|w| w[0] <= w[1]), "{:?}", impacts);
+    }
+
+    #[test]
+    fn test_marginal_price_ladder() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert!(book.marginal_price_ladder(Side::Buy).is_empty());
+
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 5000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 100500, 5000, 4).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 5).unwrap();
+        book.place_order(Side::Sell, 102000, 30000, 6).unwrap();
+
+        assert_eq!(
+            book.marginal_price_ladder(Side::Buy),
+            vec![(15000, 100500), (35000, 101000), (65000, 102000)]
+        );
+        assert_eq!(book.marginal_price_ladder(Side::Sell), vec![(10000, 99500), (15000, 99000)]);
+    }
+
+    #[test]
+    fn test_estimate_fill_partial_liquidity() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100000, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 30000, 2).unwrap();
+
+        let estimate = book.estimate_fill(Side::Buy, 20000);
+        assert_eq!(estimate.filled_quantity, 20000);
+        assert_eq!(estimate.unfilled_quantity, 0);
+        assert_eq!(estimate.average_price, Some(100500.0));
+
+        let estimate = book.estimate_fill(Side::Buy, 50000);
+        assert_eq!((estimate.filled_quantity, estimate.unfilled_quantity), (40000, 10000));
+        assert_eq!(estimate.average_price, Some(100750.0));
+
+        assert_eq!(book.estimate_fill(Side::Sell, 10000).average_price, None);
+        // Nothing was consumed
+        assert_eq!(book.best_sell(), Some((100000, 10000)));
+        assert_eq!(book.sequence(), 2);
+    }
+
+    #[test]
+    fn test_half_spread_grows_with_size() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        assert_eq!(book.half_spread(Side::Buy, 5000), Some(500));
+        assert_eq!(book.half_spread(Side::Buy, 20000), None);
+
+        book.place_order(Side::Sell, 101500, 10000, 4).unwrap();
+        assert_eq!(book.half_spread(Side::Buy, 20000), Some(1000));
+        // Sell side: 99500 then 99000, averaging 99250 against a 100000 mid
+        assert_eq!(book.half_spread(Side::Sell, 10000), Some(500));
+        assert_eq!(book.half_spread(Side::Sell, 20000), Some(750));
+    }
+
+    #[test]
+    fn test_imbalance_profile_decays_with_depth() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert!(book.imbalance_profile(3).is_empty());
+
+        // Bid-heavy touch, ask-heavy behind it so the book evens out
+        book.place_order(Side::Buy, 99500, 30000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 10000, 2).unwrap();
+        book.place_order(Side::Buy, 98500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 4).unwrap();
+        book.place_order(Side::Sell, 101000, 30000, 5).unwrap();
+        book.place_order(Side::Sell, 101500, 10000, 6).unwrap();
+
+        let profile = book.imbalance_profile(3);
+        assert_eq!(profile, vec![(1, 0.5), (2, 0.0), (3, 0.0)]);
+    }
+
+    #[test]
+    fn test_implied_carry_bid_heavy() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 30000, 1).unwrap();
+        assert_eq!(book.implied_carry(3), None);
+
+        book.place_order(Side::Buy, 99000, 20000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 4).unwrap();
+
+        // Bids 30000 + 20000/2, asks 10000 + 10000/2
+        let carry = book.implied_carry(2).unwrap();
+        assert!(carry > 0.0);
+        assert!((carry - 25000.0 / 55000.0 * MAX_IMPLIED_CARRY_BPS).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_columns_matches_rows() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 20000, 2).unwrap();
+        book.place_order(Side::Buy, 99500, 5000, 3).unwrap();
+        book.place_order(Side::Sell, 100500, 15000, 4).unwrap();
+        book.place_order(Side::Buy, 100500, 5000, 5).unwrap();
+
+        let columns = book.to_columns();
+        assert_eq!(columns.ids, vec![2, 1, 3, 4]);
+
+        let rebuilt: Vec<(u64, Side, i64, i64, u64)> = (0..columns.ids.len())
+            .map(|i| (columns.ids[i], columns.sides[i], columns.prices[i], columns.quantities[i], columns.timestamps[i]))
+            .collect();
+        let rows: Vec<(u64, Side, i64, i64, u64)> = [Side::Buy, Side::Sell]
+            .into_iter()
+            .flat_map(|side| book.levels(side).values().flatten())
+            .map(|o| (o.id, o.side, o.price, o.quantity, o.timestamp))
+            .collect();
+        assert_eq!(rebuilt, rows);
+        assert_eq!(columns.quantities[3], 10000);
+    }
+
+    #[test]
+    fn test_expected_execution_price_favors_queue_front() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 30000, 2).unwrap();
+        book.place_order(Side::Buy, 99500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 4).unwrap();
+
+        // 20000 of selling fills order 1 in full but never reaches order 3
+        let front = book.expected_execution_price(1, 20000).unwrap();
+        let back = book.expected_execution_price(3, 20000).unwrap();
+        assert_eq!(front, 99500.0);
+        assert_eq!(back, 100500.0);
+        assert!(front < back);
+
+        // Order 2 is halfway: 10000 of its 30000 fills passively
+        let middle = book.expected_execution_price(2, 20000).unwrap();
+        assert!((middle - (99500.0 / 3.0 + 100500.0 * 2.0 / 3.0)).abs() < 1e-9);
+        assert_eq!(book.expected_execution_price(99, 20000), None);
+    }
+
+    #[test]
+    fn test_churn_rate() {
+        let clock = crate::clock::MockClock::new(1_000);
+        let mut book = OrderBook::with_clock("Valhalla/USD".to_string(), std::sync::Arc::new(clock.clone()));
+        book.place_order(Side::Buy, 99000, 10000, 1).unwrap();
+        clock.set(2_000);
+        book.place_order(Side::Buy, 99500, 10000, 2).unwrap();
+        book.cancel_order(2).unwrap();
+        clock.set(4_000);
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        clock.set(5_000);
+        book.cancel_replace(1, 99500, 10000).unwrap(); // cancel + add
+        clock.set(6_000);
+        book.place_order(Side::Sell, 99500, 5000, 4).unwrap(); // trades, no add
+
+        assert_eq!(book.churn_rate(Side::Buy, 6_000, 6_000), 5.0 / 6.0);
+        assert_eq!(book.churn_rate(Side::Buy, 3_000, 6_000), 2.0 / 3.0);
+        assert_eq!(book.churn_rate(Side::Sell, 6_000, 6_000), 1.0 / 6.0);
+        assert_eq!(book.churn_rate(Side::Sell, 2_000, 6_000), 0.0);
+        // A quiet stretch of clock time has no churn, whatever the event count
+        assert_eq!(book.churn_rate(Side::Buy, 1_000, 60_000), 0.0);
+    }
+
+    #[test]
+    fn test_quoted_value_top_three_levels() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        // Same ladder the engine seeds
+        let seed = [
+            (Side::Buy, 99500, 150000), (Side::Buy, 99000, 250000),
+            (Side::Buy, 98500, 350000), (Side::Buy, 98000, 450000),
+            (Side::Sell, 100500, 200000), (Side::Sell, 101000, 300000),
+            (Side::Sell, 101500, 400000), (Side::Sell, 102000, 500000),
+        ];
+        for (id, (side, price, quantity)) in seed.into_iter().enumerate() {
+            book.place_order(side, price, quantity, id as u64).unwrap();
+        }
+
+        let (bids, asks) = book.quoted_value(3);
+        assert_eq!(bids, 99500 * 150000 + 99000 * 250000 + 98500 * 350000);
+        assert_eq!(asks, 100500 * 200000 + 101000 * 300000 + 101500 * 400000);
+        assert_eq!(book.quoted_value(0), (0, 0));
+    }
+}
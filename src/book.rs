@@ -0,0 +1,2917 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::OrderError;
+use crate::render::RenderConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Buy => write!(f, "BUY"),
+            Side::Sell => write!(f, "SELL"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Order {
+    pub id: u64,
+    pub side: Side,
+    pub price: i64,
+    pub quantity: i64,
+    /// Book sequence number at entry; orders at a price fill in this order.
+    pub timestamp: u64,
+    /// Clock time (unix millis) when the order rested.
+    pub created_at: u64,
+    /// Book event count when the order was placed.
+    pub placed_event: u64,
+    /// Remove the order once this many further book events have occurred.
+    pub expire_after_events: Option<u64>,
+    /// Clock time (unix millis) from which the order no longer trades.
+    pub expires_at: Option<u64>,
+    /// Account the order belongs to; 0 (no owner) unless placed with
+    /// `place_order_for`.
+    pub owner_id: u64,
+    /// Quantity filled while resting, across all its fills.
+    pub filled: i64,
+}
+
+impl Order {
+    /// Whether a good-till-time order has reached its expiry at `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    pub price: i64,
+    pub quantity: i64,
+    pub maker_id: u64,
+    pub taker_id: u64,
+    /// Side of the incoming (taker) order.
+    pub aggressor: Side,
+    /// Side of the resting (maker) order.
+    pub maker_side: Side,
+    pub maker_owner: u64,
+    pub taker_owner: u64,
+}
+
+/// Tick sizes by price band. Each band starts at a price (in cents) and
+/// applies until the next band starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickTable {
+    bands: Vec<(i64, i64)>,
+}
+
+impl TickTable {
+    /// A single tick size for every price.
+    pub fn new(tick: i64) -> Self {
+        assert!(tick > 0, "tick size must be positive");
+        Self { bands: vec![(0, tick)] }
+    }
+
+    /// Uses `tick` for prices at or above `from_price`, up to the next band.
+    pub fn with_band(mut self, from_price: i64, tick: i64) -> Self {
+        assert!(tick > 0, "tick size must be positive");
+        self.bands.retain(|&(start, _)| start != from_price);
+        self.bands.push((from_price, tick));
+        self.bands.sort_unstable();
+        self
+    }
+
+    pub fn tick_for(&self, price: i64) -> i64 {
+        self.bands
+            .iter()
+            .rev()
+            .find(|&&(start, _)| start <= price)
+            .or(self.bands.first())
+            .map(|&(_, tick)| tick)
+            .unwrap_or(1)
+    }
+
+    pub fn is_on_tick(&self, price: i64) -> bool {
+        price % self.tick_for(price) == 0
+    }
+}
+
+impl Default for TickTable {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TradingState {
+    #[default]
+    Continuous,
+    Halted,
+}
+
+/// What happens to orders entered while the book is halted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HaltPolicy {
+    /// Reject with `OrderError::Halted`.
+    #[default]
+    Reject,
+    /// Park the order and release it into matching on `resume`.
+    Queue,
+}
+
+#[derive(Debug, Clone)]
+pub struct BookConfig {
+    /// When false, an order priced exactly at the opposite touch rests
+    /// (locking the market) instead of trading against it.
+    pub cross_on_equal: bool,
+    pub tick_table: TickTable,
+    /// Record the best bid/ask after every order, and the sequence number of
+    /// every trade, so `bbo_drift` and `spread_efficiency` can look back at
+    /// earlier sequence numbers.
+    pub record_bbo_history: bool,
+    /// How far `skew_fair_value` leans on depth pressure, as a fraction of
+    /// the half-spread.
+    pub skew_weight: f64,
+    pub halt_policy: HaltPolicy,
+    /// Record each matching decision for `take_trace`. Off by default.
+    pub trace: bool,
+    /// Cap on resting orders across both sides. When exceeded, orders on the
+    /// levels furthest from their touch are evicted (see `take_evicted`).
+    pub max_total_orders: Option<usize>,
+    /// Let `cancel_replace` keep time priority when it only lowers quantity.
+    pub keep_priority_on_downsize: bool,
+    pub matching_policy: MatchingPolicy,
+    pub odd_lot_policy: OddLotPolicy,
+    pub self_trade_prevention: SelfTradePrevention,
+    /// Orders larger than this are rejected before matching.
+    pub max_order_quantity: Option<i64>,
+    /// Stops matching before a print more than this many basis points away
+    /// from the last trade price; the aggressor's remainder is cancelled.
+    pub trade_price_band_bps: Option<f64>,
+    /// Penalty charged to market orders, in basis points of fill notional
+    /// per level swept past the touch: fills at the touch are free, fills
+    /// one level further cost this many bps, two levels twice that, etc.
+    pub market_slippage_bps_per_level: i64,
+    /// Reject resting orders that would lock or cross the protected
+    /// external quote set with `OrderBook::set_reference_bbo`.
+    pub lock_protection: bool,
+}
+
+impl Default for BookConfig {
+    fn default() -> Self {
+        Self {
+            cross_on_equal: true,
+            tick_table: TickTable::default(),
+            record_bbo_history: false,
+            skew_weight: 0.5,
+            halt_policy: HaltPolicy::Reject,
+            trace: false,
+            max_total_orders: None,
+            keep_priority_on_downsize: false,
+            matching_policy: MatchingPolicy::PriceTime,
+            odd_lot_policy: OddLotPolicy::Match,
+            self_trade_prevention: SelfTradePrevention::Off,
+            max_order_quantity: None,
+            trade_price_band_bps: None,
+            market_slippage_bps_per_level: 0,
+            lock_protection: false,
+        }
+    }
+}
+
+/// Running totals for the current trading session. Notional is price in
+/// cents times quantity in 0.0001 units, i.e. in the quote currency at the
+/// book's internal scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionStats {
+    pub volume: i64,
+    pub notional: i128,
+    pub trade_count: u64,
+    /// Highest and lowest trade prices of the session.
+    pub high: Option<i64>,
+    pub low: Option<i64>,
+    /// Quote-to-reporting currency rate used by
+    /// `notional_in_reporting_currency`.
+    pub fx_rate: f64,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            volume: 0,
+            notional: 0,
+            trade_count: 0,
+            high: None,
+            low: None,
+            fx_rate: 1.0,
+        }
+    }
+}
+
+impl SessionStats {
+    fn record(&mut self, trade: &Trade) {
+        self.volume = self.volume.saturating_add(trade.quantity);
+        self.notional += trade.price as i128 * trade.quantity as i128;
+        self.trade_count += 1;
+        self.high = Some(self.high.map_or(trade.price, |high| high.max(trade.price)));
+        self.low = Some(self.low.map_or(trade.price, |low| low.min(trade.price)));
+    }
+
+    /// Volume-weighted average trade price in cents, `None` before the
+    /// first trade.
+    pub fn vwap(&self) -> Option<f64> {
+        (self.volume > 0).then(|| self.notional as f64 / self.volume as f64)
+    }
+
+    pub fn notional_in_reporting_currency(&self) -> f64 {
+        self.notional as f64 * self.fx_rate
+    }
+}
+
+/// How long an order's unfilled remainder stays on the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeInForce {
+    /// Good till cancelled: the remainder rests.
+    #[default]
+    Gtc,
+    /// Immediate or cancel: fill what crosses now, discard the rest.
+    Ioc,
+    /// Fill or kill: fill the whole quantity now or do nothing.
+    Fok,
+}
+
+/// Everything needed to enter an order, shared by the public placement
+/// entry points.
+#[derive(Debug, Clone)]
+pub(crate) struct NewOrder {
+    pub side: Side,
+    pub price: i64,
+    pub quantity: i64,
+    pub id: u64,
+    pub expire_after_events: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub owner_id: u64,
+    pub time_in_force: TimeInForce,
+    /// No price limit: sweeps until filled or the book runs dry. Always IOC.
+    pub market: bool,
+    /// Reject rather than take liquidity.
+    pub post_only: bool,
+}
+
+impl NewOrder {
+    pub fn limit(side: Side, price: i64, quantity: i64, id: u64) -> Self {
+        Self {
+            side,
+            price,
+            quantity,
+            id,
+            expire_after_events: None,
+            expires_at: None,
+            owner_id: 0,
+            time_in_force: TimeInForce::Gtc,
+            market: false,
+            post_only: false,
+        }
+    }
+
+    pub fn market(side: Side, quantity: i64, id: u64) -> Self {
+        // A limit no resting price can fail to cross
+        let price = match side {
+            Side::Buy => i64::MAX,
+            Side::Sell => i64::MIN,
+        };
+        Self {
+            time_in_force: TimeInForce::Ioc,
+            market: true,
+            ..Self::limit(side, price, quantity, id)
+        }
+    }
+}
+
+/// Id of the synthetic bid left by `OrderBook::compact_tail`.
+pub const COMPACTED_BID_ID: u64 = u64::MAX - 1;
+/// Id of the synthetic ask left by `OrderBook::compact_tail`.
+pub const COMPACTED_ASK_ID: u64 = u64::MAX;
+
+/// Result of `OrderBook::place_market_order`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MarketFill {
+    pub trades: Vec<Trade>,
+    /// Quantity left when the opposite side ran out; discarded, never rested.
+    pub unfilled: i64,
+    /// Charge under `BookConfig::market_slippage_bps_per_level`, in notional
+    /// units (cents * 0.0001 units), saturating at `i64::MAX`.
+    pub slippage_penalty: i64,
+}
+
+/// How an incoming order's quantity is shared among the resting orders at
+/// one price level.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MatchingPolicy {
+    /// Strict FIFO within the level.
+    #[default]
+    PriceTime,
+    /// The front order first receives up to `top_order_fraction` of the
+    /// quantity filled at the level; the rest is shared pro-rata across all
+    /// orders at the level by remaining size (the front order included).
+    Hybrid { top_order_fraction: f64 },
+    /// The quantity filled at a level is shared across all its orders in
+    /// proportion to their size; rounding leftovers go out in queue order
+    /// (see `pro_rata_allocation`).
+    ProRata,
+}
+
+/// What happens when an incoming order would trade with a resting order of
+/// the same owner. Orders without an owner (`owner_id` 0) are never
+/// treated as self-trades.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelfTradePrevention {
+    /// Let the orders trade.
+    #[default]
+    Off,
+    /// Cancel the resting order and keep matching against the rest of the
+    /// book.
+    CancelResting,
+}
+
+/// Whether price-time matching trades quantities that aren't whole lots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OddLotPolicy {
+    /// Trade any quantity.
+    #[default]
+    Match,
+    /// Each fill is a multiple of `lot_size`. Resting orders holding less
+    /// than a lot are passed over, and an aggressor's sub-lot remainder rests
+    /// without crossing, so the book can be left locked or crossed by odd
+    /// lots until they are handled separately. Hybrid and pro-rata matching
+    /// ignore it. `lot_size` must be positive.
+    RoundLotsOnly { lot_size: i64 },
+}
+
+/// Total resting quantity at a level, saturating at `i64::MAX` rather than
+/// wrapping.
+pub(crate) fn level_quantity(orders: &VecDeque<Order>) -> i64 {
+    orders.iter().fold(0i64, |total, o| total.saturating_add(o.quantity))
+}
+
+/// Splits `quantity` (at most `sizes.iter().sum()`) across orders in
+/// proportion to `sizes`. Each order gets `floor(quantity * size / total)`;
+/// units lost to rounding then go one at a time to orders in queue order
+/// that still have room.
+fn pro_rata_allocation(sizes: &[i64], quantity: i64) -> Vec<i64> {
+    let total: i128 = sizes.iter().map(|&size| size as i128).sum();
+    if total <= 0 || quantity <= 0 {
+        return vec![0; sizes.len()];
+    }
+
+    let mut allocations: Vec<i64> = sizes
+        .iter()
+        .map(|&size| (quantity as i128 * size as i128 / total) as i64)
+        .collect();
+
+    let mut leftover = quantity - allocations.iter().sum::<i64>();
+    while leftover > 0 {
+        for (allocation, &size) in allocations.iter_mut().zip(sizes) {
+            if leftover > 0 && *allocation < size {
+                *allocation += 1;
+                leftover -= 1;
+            }
+        }
+    }
+    allocations
+}
+
+/// Trades (and trace lines) produced while matching one incoming order.
+struct Fills {
+    taker_id: u64,
+    taker_owner: u64,
+    aggressor: Side,
+    /// Owner whose resting orders are cancelled instead of traded against.
+    stp_owner: Option<u64>,
+    trades: Vec<Trade>,
+    /// Ids of resting orders that left the book (filled or cancelled).
+    removed: Vec<u64>,
+    trace: Option<Vec<String>>,
+    /// Clock time of the match.
+    now: u64,
+    /// Sum of quantity x time rested (millis) over the makers filled.
+    rested_millis_qty: i128,
+    /// (id, price, total filled) of resting orders filled completely.
+    completed: Vec<(u64, i64, i64)>,
+}
+
+impl Fills {
+    fn note(&mut self, line: impl FnOnce() -> String) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(line());
+        }
+    }
+
+    fn fill(&mut self, resting_order: &mut Order, trade_qty: i64) {
+        let taker_id = self.taker_id;
+        self.note(|| format!("trade {} @ {} (maker #{}, taker #{})",
+            trade_qty, resting_order.price, resting_order.id, taker_id));
+
+        self.trades.push(Trade {
+            price: resting_order.price,
+            quantity: trade_qty,
+            maker_id: resting_order.id,
+            taker_id,
+            aggressor: self.aggressor,
+            maker_side: resting_order.side,
+            maker_owner: resting_order.owner_id,
+            taker_owner: self.taker_owner,
+        });
+        let rested = self.now.saturating_sub(resting_order.created_at);
+        self.rested_millis_qty += rested as i128 * trade_qty as i128;
+        resting_order.quantity -= trade_qty;
+        resting_order.filled += trade_qty;
+        if resting_order.quantity == 0 {
+            self.completed.push((resting_order.id, resting_order.price, resting_order.filled));
+        }
+    }
+
+    fn price_time(&mut self, orders: &mut VecDeque<Order>, remaining_qty: &mut i64, lot_size: i64) {
+        let mut index = 0;
+        while index < orders.len() && *remaining_qty >= lot_size {
+            if self.stp_owner == Some(orders[index].owner_id) {
+                let cancelled = orders.remove(index).expect("index is in bounds");
+                self.note(|| format!("self-trade: cancel order #{}", cancelled.id));
+                self.removed.push(cancelled.id);
+                continue;
+            }
+            let resting_order = &mut orders[index];
+            let trade_qty = std::cmp::min(*remaining_qty, resting_order.quantity) / lot_size * lot_size;
+            if trade_qty == 0 {
+                // Less than a lot resting: leave it and look further back
+                index += 1;
+                continue;
+            }
+
+            self.note(|| format!("pop order #{} ({} resting)", resting_order.id, resting_order.quantity));
+            self.fill(resting_order, trade_qty);
+            *remaining_qty -= trade_qty;
+
+            if resting_order.quantity == 0 {
+                self.removed.push(resting_order.id);
+                orders.remove(index);
+            }
+        }
+    }
+
+    fn hybrid(&mut self, orders: &mut VecDeque<Order>, remaining_qty: &mut i64, top_order_fraction: f64) {
+        if let Some(owner) = self.stp_owner {
+            self.retain(orders, |o| o.owner_id != owner);
+            if orders.is_empty() {
+                return;
+            }
+        }
+        let level_qty = level_quantity(orders);
+        let fill_qty = std::cmp::min(*remaining_qty, level_qty);
+        let top_qty = std::cmp::min(
+            orders[0].quantity,
+            (fill_qty as f64 * top_order_fraction.clamp(0.0, 1.0)).floor() as i64,
+        );
+
+        let sizes: Vec<i64> = orders
+            .iter()
+            .enumerate()
+            .map(|(i, o)| if i == 0 { o.quantity - top_qty } else { o.quantity })
+            .collect();
+        let mut allocations = pro_rata_allocation(&sizes, fill_qty - top_qty);
+        allocations[0] += top_qty;
+
+        for (resting_order, trade_qty) in orders.iter_mut().zip(allocations) {
+            if trade_qty > 0 {
+                self.fill(resting_order, trade_qty);
+            }
+        }
+        self.retain(orders, |o| o.quantity > 0);
+        *remaining_qty -= fill_qty;
+    }
+
+    /// Removes resting orders that expired before matching reached them.
+    fn drop_expired(&mut self, orders: &mut VecDeque<Order>) {
+        let now = self.now;
+        if self.trace.is_some() {
+            for o in orders.iter().filter(|o| o.is_expired(now)) {
+                let id = o.id;
+                self.note(|| format!("expired: drop order #{}", id));
+            }
+        }
+        self.retain(orders, |o| !o.is_expired(now));
+    }
+
+    fn retain(&mut self, orders: &mut VecDeque<Order>, keep: impl Fn(&Order) -> bool) {
+        orders.retain(|o| {
+            let kept = keep(o);
+            if !kept {
+                self.removed.push(o.id);
+            }
+            kept
+        });
+    }
+}
+
+/// Point-in-time L2 view of the book: `(price, total quantity)` per level,
+/// best first on each side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthSnapshot {
+    pub symbol: String,
+    pub bids: Vec<(i64, i64)>,
+    pub asks: Vec<(i64, i64)>,
+    /// Best ask minus best bid, `None` for a one-sided book.
+    pub spread: Option<i64>,
+}
+
+/// Callback fired for each trade; see `OrderBook::set_on_trade`.
+pub type TradeCallback = Box<dyn FnMut(&Trade) + Send>;
+
+/// Callback fired with `(id, final price, total filled)` when a resting
+/// order is filled completely; see `OrderBook::set_on_order_filled`.
+pub type OrderFilledCallback = Box<dyn FnMut(u64, i64, i64) + Send>;
+
+struct Hook<F>(Option<F>);
+
+impl<F> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(<callback>)" } else { "None" })
+    }
+}
+
+/// One aggregated price level of a `Ladder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LadderLevel {
+    pub price: i64,
+    pub quantity: i64,
+    pub order_count: usize,
+}
+
+/// Aggregated levels with order counts, best first on each side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ladder {
+    pub bids: Vec<LadderLevel>,
+    pub asks: Vec<LadderLevel>,
+}
+
+/// In-memory copy of everything a book's trading has changed: levels,
+/// sequence and event counters, stats, last price and pending orders. See
+/// `OrderBook::export_state`.
+#[derive(Debug, Clone)]
+pub struct BookState {
+    buy_levels: BTreeMap<i64, VecDeque<Order>>,
+    sell_levels: BTreeMap<i64, VecDeque<Order>>,
+    next_timestamp: u64,
+    bbo_history: Vec<(u64, Option<i64>, Option<i64>)>,
+    trade_log: Vec<(u64, i64, Side)>,
+    stats: SessionStats,
+    past_sessions: Vec<SessionStats>,
+    state: TradingState,
+    trace_log: Vec<String>,
+    evicted: Vec<Order>,
+    parked_orders: VecDeque<NewOrder>,
+    event_count: u64,
+    level_updates: HashMap<(Side, i64), u64>,
+    event_expiries: BTreeMap<u64, Vec<u64>>,
+    last_trade_price: Option<i64>,
+    account_notional: HashMap<u64, i128>,
+    account_volume: HashMap<u64, (i64, i64)>,
+    order_index: HashMap<u64, (Side, i64)>,
+    stop_orders: Vec<(i64, NewOrder)>,
+    churn_log: Vec<(u64, Side)>,
+    time_in_book: (i128, i128),
+}
+
+#[derive(Debug)]
+pub struct OrderBook {
+    pub(crate) buy_levels: BTreeMap<i64, VecDeque<Order>>,
+    pub(crate) sell_levels: BTreeMap<i64, VecDeque<Order>>,
+    pub(crate) next_timestamp: u64,
+    symbol: String,
+    config: BookConfig,
+    pub(crate) bbo_history: Vec<(u64, Option<i64>, Option<i64>)>,
+    // (taker sequence number, price, aggressor) per trade, with the BBO history
+    pub(crate) trade_log: Vec<(u64, i64, Side)>,
+    render_config: RenderConfig,
+    stats: SessionStats,
+    past_sessions: Vec<SessionStats>,
+    state: TradingState,
+    trace_log: Vec<String>,
+    evicted: Vec<Order>,
+    parked_orders: VecDeque<NewOrder>,
+    pub(crate) event_count: u64,
+    // Event count of the last change to each occupied level
+    level_updates: HashMap<(Side, i64), u64>,
+    // Event count at which an order expires -> order ids
+    event_expiries: BTreeMap<u64, Vec<u64>>,
+    last_trade_price: Option<i64>,
+    // Gross traded notional per owner, across sessions
+    account_notional: HashMap<u64, i128>,
+    // (maker volume, taker volume) per owner, across sessions
+    account_volume: HashMap<u64, (i64, i64)>,
+    // Resting order id -> (side, price) of its level
+    order_index: HashMap<u64, (Side, i64)>,
+    on_trade: Hook<TradeCallback>,
+    on_order_filled: Hook<OrderFilledCallback>,
+    // (trigger price, order) for stops not yet triggered, in placement order
+    stop_orders: Vec<(i64, NewOrder)>,
+    // (clock millis, side) of each order added to or cancelled from the book
+    pub(crate) churn_log: Vec<(u64, Side)>,
+    clock: Arc<dyn Clock>,
+    // Shadow book replaying every order entry and cancel made here
+    mirror: Option<Box<OrderBook>>,
+    // (sum of filled quantity x millis rested, filled quantity) over all maker fills
+    pub(crate) time_in_book: (i128, i128),
+    // Protected external (bid, ask) for lock protection
+    reference_bbo: (Option<i64>, Option<i64>),
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl OrderBook {
+    pub fn new(symbol: String) -> Self {
+        Self::with_config(symbol, BookConfig::default())
+    }
+
+    pub fn with_config(symbol: String, config: BookConfig) -> Self {
+        if let OddLotPolicy::RoundLotsOnly { lot_size } = config.odd_lot_policy {
+            assert!(lot_size > 0, "lot size must be positive");
+        }
+        Self {
+            buy_levels: BTreeMap::new(),
+            sell_levels: BTreeMap::new(),
+            next_timestamp: 1,
+            symbol,
+            config,
+            bbo_history: Vec::new(),
+            trade_log: Vec::new(),
+            render_config: RenderConfig::default(),
+            stats: SessionStats::default(),
+            past_sessions: Vec::new(),
+            state: TradingState::Continuous,
+            trace_log: Vec::new(),
+            evicted: Vec::new(),
+            parked_orders: VecDeque::new(),
+            event_count: 0,
+            level_updates: HashMap::new(),
+            event_expiries: BTreeMap::new(),
+            last_trade_price: None,
+            account_notional: HashMap::new(),
+            account_volume: HashMap::new(),
+            order_index: HashMap::new(),
+            on_trade: Hook(None),
+            on_order_filled: Hook(None),
+            stop_orders: Vec::new(),
+            churn_log: Vec::new(),
+            clock: Arc::new(SystemClock),
+            mirror: None,
+            time_in_book: (0, 0),
+            reference_bbo: (None, None),
+        }
+    }
+
+    /// A book with the default config whose order timestamps come from
+    /// `clock`.
+    pub fn with_clock(symbol: String, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..Self::new(symbol) }
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn config(&self) -> &BookConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut BookConfig {
+        &mut self.config
+    }
+
+    pub fn render_config(&self) -> &RenderConfig {
+        &self.render_config
+    }
+
+    pub fn render_config_mut(&mut self) -> &mut RenderConfig {
+        &mut self.render_config
+    }
+
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    /// Registers a callback run for every trade, in match order, before the
+    /// placing call returns. Replaces any earlier callback.
+    pub fn set_on_trade(&mut self, f: TradeCallback) {
+        self.on_trade = Hook(Some(f));
+    }
+
+    pub fn clear_on_trade(&mut self) {
+        self.on_trade = Hook(None);
+    }
+
+    /// Registers a callback run each time matching fills a resting order
+    /// down to zero, after that match's trade callbacks. Partial fills don't
+    /// fire it. Replaces any earlier callback.
+    pub fn set_on_order_filled(&mut self, f: OrderFilledCallback) {
+        self.on_order_filled = Hook(Some(f));
+    }
+
+    pub fn clear_on_order_filled(&mut self) {
+        self.on_order_filled = Hook(None);
+    }
+
+    /// Attaches a shadow book that is sent every order entry, cancel,
+    /// amend and expiry sweep applied to this one, replacing any earlier
+    /// mirror. The mirror keeps its own config but takes this book's clock
+    /// so good-till-time expiry agrees. Trades on the mirror are its own;
+    /// nothing flows back.
+    pub fn attach_mirror(&mut self, mut mirror: OrderBook) {
+        mirror.clock = self.clock.clone();
+        self.mirror = Some(Box::new(mirror));
+    }
+
+    pub fn detach_mirror(&mut self) -> Option<OrderBook> {
+        self.mirror.take().map(|mirror| *mirror)
+    }
+
+    pub fn mirror(&self) -> Option<&OrderBook> {
+        self.mirror.as_deref()
+    }
+
+    /// Whether the mirror's full ladder (prices, quantities and order counts)
+    /// matches this book's, or `None` without a mirror.
+    pub fn mirror_in_sync(&self) -> Option<bool> {
+        let mirror = self.mirror.as_deref()?;
+        Some(mirror.ladder(usize::MAX) == self.ladder(usize::MAX))
+    }
+
+    fn replicate(&mut self, op: impl FnOnce(&mut OrderBook)) {
+        if let Some(mirror) = self.mirror.as_deref_mut() {
+            op(mirror);
+        }
+    }
+
+    /// Highest trade price of the current session.
+    pub fn session_high(&self) -> Option<i64> {
+        self.stats.high
+    }
+
+    /// Lowest trade price of the current session.
+    pub fn session_low(&self) -> Option<i64> {
+        self.stats.low
+    }
+
+    /// Gross notional (cents * quantity units) of every trade `account_id`
+    /// took part in as maker or taker, counted once per trade.
+    pub fn account_notional(&self, account_id: u64) -> i128 {
+        self.account_notional.get(&account_id).copied().unwrap_or(0)
+    }
+
+    /// `(maker volume, taker volume)` `account_id` has traded: quantity
+    /// filled while resting versus while aggressing.
+    pub fn passive_aggressive_split(&self, account_id: u64) -> (i64, i64) {
+        self.account_volume.get(&account_id).copied().unwrap_or((0, 0))
+    }
+
+    /// Stats of earlier sessions, oldest first.
+    pub fn past_sessions(&self) -> &[SessionStats] {
+        &self.past_sessions
+    }
+
+    /// Archives the current session's stats and starts a fresh session.
+    /// Resting orders and the FX rate carry over.
+    pub fn roll_session(&mut self) {
+        let fresh = SessionStats { fx_rate: self.stats.fx_rate, ..SessionStats::default() };
+        self.past_sessions.push(std::mem::replace(&mut self.stats, fresh));
+    }
+
+    /// Sets the protected external best bid and ask checked when
+    /// `BookConfig::lock_protection` is on. `None` leaves that side
+    /// unprotected.
+    pub fn set_reference_bbo(&mut self, bid: Option<i64>, ask: Option<i64>) {
+        self.reference_bbo = (bid, ask);
+    }
+
+    /// Copies the book's trading state without serializing it, e.g. to
+    /// rewind a test harness. Symbol, config, clock, trade callback and
+    /// mirror are not part of the state.
+    pub fn export_state(&self) -> BookState {
+        BookState {
+            buy_levels: self.buy_levels.clone(),
+            sell_levels: self.sell_levels.clone(),
+            next_timestamp: self.next_timestamp,
+            bbo_history: self.bbo_history.clone(),
+            trade_log: self.trade_log.clone(),
+            stats: self.stats.clone(),
+            past_sessions: self.past_sessions.clone(),
+            state: self.state,
+            trace_log: self.trace_log.clone(),
+            evicted: self.evicted.clone(),
+            parked_orders: self.parked_orders.clone(),
+            event_count: self.event_count,
+            level_updates: self.level_updates.clone(),
+            event_expiries: self.event_expiries.clone(),
+            last_trade_price: self.last_trade_price,
+            account_notional: self.account_notional.clone(),
+            account_volume: self.account_volume.clone(),
+            order_index: self.order_index.clone(),
+            stop_orders: self.stop_orders.clone(),
+            churn_log: self.churn_log.clone(),
+            time_in_book: self.time_in_book,
+        }
+    }
+
+    /// Replaces the book's trading state with `state`, as exported earlier
+    /// by this or another book. Symbol, config, clock, trade callback and
+    /// mirror are kept.
+    pub fn import_state(&mut self, state: BookState) {
+        let BookState {
+            buy_levels,
+            sell_levels,
+            next_timestamp,
+            bbo_history,
+            trade_log,
+            stats,
+            past_sessions,
+            state,
+            trace_log,
+            evicted,
+            parked_orders,
+            event_count,
+            level_updates,
+            event_expiries,
+            last_trade_price,
+            account_notional,
+            account_volume,
+            order_index,
+            stop_orders,
+            churn_log,
+            time_in_book,
+        } = state;
+        self.buy_levels = buy_levels;
+        self.sell_levels = sell_levels;
+        self.next_timestamp = next_timestamp;
+        self.bbo_history = bbo_history;
+        self.trade_log = trade_log;
+        self.stats = stats;
+        self.past_sessions = past_sessions;
+        self.state = state;
+        self.trace_log = trace_log;
+        self.evicted = evicted;
+        self.parked_orders = parked_orders;
+        self.event_count = event_count;
+        self.level_updates = level_updates;
+        self.event_expiries = event_expiries;
+        self.last_trade_price = last_trade_price;
+        self.account_notional = account_notional;
+        self.account_volume = account_volume;
+        self.order_index = order_index;
+        self.stop_orders = stop_orders;
+        self.churn_log = churn_log;
+        self.time_in_book = time_in_book;
+    }
+
+    /// Sets the rate converting quote-currency notional into the reporting
+    /// currency. Matching and native-currency stats are unaffected.
+    pub fn set_fx_rate(&mut self, rate: f64) {
+        self.stats.fx_rate = rate;
+    }
+
+    pub fn state(&self) -> TradingState {
+        self.state
+    }
+
+    pub fn halt(&mut self) {
+        self.state = TradingState::Halted;
+    }
+
+    /// Reopens continuous trading, releasing any orders parked during the
+    /// halt in arrival order. Returns the trades they produced.
+    pub fn resume(&mut self) -> Vec<Trade> {
+        self.state = TradingState::Continuous;
+        let mut trades = Vec::new();
+        while let Some(order) = self.parked_orders.pop_front() {
+            // Parked orders were validated on entry
+            if let Ok(fills) = self.submit(order) {
+                trades.extend(fills);
+            }
+        }
+        trades
+    }
+
+    /// `(price, last_update)` for each occupied level on `side`, best price
+    /// first. `last_update` is the book event count at the level's most recent
+    /// add, fill or reduction.
+    pub fn level_timestamps(&self, side: Side) -> Vec<(i64, u64)> {
+        self.levels_from_touch(side)
+            .map(|(&price, _)| (price, self.level_updates.get(&(side, price)).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// Number of resting orders across both sides.
+    pub fn order_count(&self) -> usize {
+        self.buy_levels
+            .values()
+            .chain(self.sell_levels.values())
+            .map(|orders| orders.len())
+            .sum()
+    }
+
+    /// The resting order with this id, reflecting any partial fills.
+    pub fn get_order(&self, id: u64) -> Option<&Order> {
+        let (side, price, index) = self.locate_order(id)?;
+        self.levels(side)[&price].get(index)
+    }
+
+    /// Whether an order with this id is resting on the book.
+    pub fn contains(&self, id: u64) -> bool {
+        self.order_index.contains_key(&id)
+    }
+
+    /// Drains the orders evicted to stay within `max_total_orders`.
+    pub fn take_evicted(&mut self) -> Vec<Order> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// Drains the matching trace recorded while `BookConfig::trace` is on.
+    pub fn take_trace(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.trace_log)
+    }
+
+    /// Sequence number assigned to the most recently placed order.
+    pub fn sequence(&self) -> u64 {
+        self.next_timestamp.saturating_sub(1)
+    }
+
+    /// Places a good-till-cancelled limit order. `id` must not belong to an
+    /// order still resting on the book.
+    pub fn place_order(&mut self, side: Side, price: i64, quantity: i64, id: u64) -> Result<Vec<Trade>, OrderError> {
+        self.submit(NewOrder::limit(side, price, quantity, id))
+    }
+
+    /// Places an order on behalf of `owner_id`, the account checked by
+    /// self-trade prevention.
+    pub fn place_order_for(
+        &mut self,
+        owner_id: u64,
+        side: Side,
+        price: i64,
+        quantity: i64,
+        id: u64,
+    ) -> Result<Vec<Trade>, OrderError> {
+        let mut order = NewOrder::limit(side, price, quantity, id);
+        order.owner_id = owner_id;
+        self.submit(order)
+    }
+
+    /// Places an order that is removed from the book once
+    /// `expire_after_events` further book events have happened.
+    pub fn place_order_expiring(
+        &mut self,
+        side: Side,
+        price: i64,
+        quantity: i64,
+        id: u64,
+        expire_after_events: u64,
+    ) -> Result<Vec<Trade>, OrderError> {
+        let mut order = NewOrder::limit(side, price, quantity, id);
+        order.expire_after_events = Some(expire_after_events);
+        self.submit(order)
+    }
+
+    /// Places a good-till-time order: it stops trading once the book's clock
+    /// reaches `expires_at` (unix millis) and is removed by `expire_orders`,
+    /// or lazily when matching reaches it.
+    pub fn place_order_until(
+        &mut self,
+        side: Side,
+        price: i64,
+        quantity: i64,
+        id: u64,
+        expires_at: u64,
+    ) -> Result<Vec<Trade>, OrderError> {
+        let mut order = NewOrder::limit(side, price, quantity, id);
+        order.expires_at = Some(expires_at);
+        self.submit(order)
+    }
+
+    /// Removes every resting order whose `expires_at` is at or before `now`,
+    /// returning them bids first, in queue order.
+    pub fn expire_orders(&mut self, now: u64) -> Vec<Order> {
+        self.replicate(|mirror| {
+            mirror.expire_orders(now);
+        });
+        let expired: Vec<u64> = [Side::Buy, Side::Sell]
+            .into_iter()
+            .flat_map(|side| self.levels(side).values().flatten())
+            .filter(|o| o.is_expired(now))
+            .map(|o| o.id)
+            .collect();
+        expired.into_iter().filter_map(|id| self.remove_order(id)).collect()
+    }
+
+    /// Places a limit order whose unfilled remainder is handled per `tif`.
+    /// A FOK order that can't be filled in full trades nothing and returns
+    /// no trades.
+    pub fn place_order_tif(
+        &mut self,
+        side: Side,
+        price: i64,
+        quantity: i64,
+        id: u64,
+        tif: TimeInForce,
+    ) -> Result<Vec<Trade>, OrderError> {
+        let mut order = NewOrder::limit(side, price, quantity, id);
+        order.time_in_force = tif;
+        self.submit(order)
+    }
+
+    /// Holds an order off the book until the last trade price reaches
+    /// `trigger_price` (at or above it for a buy, at or below for a sell),
+    /// then enters it as a market order, or as a limit order at
+    /// `limit_price` for a stop-limit. A stop whose trigger has already been
+    /// reached activates immediately. Returns any trades from activation.
+    /// A stop-limit is checked against lock protection when placed; one the
+    /// reference quote has moved onto by the time it triggers is dropped.
+    pub fn place_stop_order(
+        &mut self,
+        side: Side,
+        trigger_price: i64,
+        limit_price: Option<i64>,
+        quantity: i64,
+        id: u64,
+    ) -> Result<Vec<Trade>, OrderError> {
+        let order = match limit_price {
+            Some(price) => {
+                self.validate(price, quantity)?;
+                self.check_lock(side, price)?;
+                NewOrder::limit(side, price, quantity, id)
+            }
+            None => {
+                self.validate_quantity(quantity)?;
+                NewOrder::market(side, quantity, id)
+            }
+        };
+        self.stop_orders.push((trigger_price, order));
+        Ok(self.activate_stops())
+    }
+
+    /// Number of stop orders waiting for their trigger.
+    pub fn pending_stop_count(&self) -> usize {
+        self.stop_orders.len()
+    }
+
+    /// Places a group of limit orders all-or-nothing. Every order is checked
+    /// first (entry rules and lock protection, plus ids unique within the
+    /// batch and not already resting); if any fails, the book is left untouched and the first
+    /// error is returned. Otherwise the orders are placed in sequence, so
+    /// later ones may trade against earlier ones, and all trades returned.
+    pub fn place_batch(&mut self, orders: &[(Side, i64, i64, u64)]) -> Result<Vec<Trade>, OrderError> {
+        if self.state == TradingState::Halted && self.config.halt_policy == HaltPolicy::Reject {
+            return Err(OrderError::Halted);
+        }
+        let mut ids = HashSet::new();
+        for &(side, price, quantity, id) in orders {
+            self.validate(price, quantity)?;
+            self.check_lock(side, price)?;
+            if self.contains(id) || !ids.insert(id) {
+                return Err(OrderError::DuplicateOrderId(id));
+            }
+        }
+
+        let mut trades = Vec::new();
+        for &(side, price, quantity, id) in orders {
+            trades.extend(self.submit(NewOrder::limit(side, price, quantity, id))?);
+        }
+        Ok(trades)
+    }
+
+    /// Places an order that may only add liquidity. If it would trade on
+    /// arrival (by the same touch test matching uses, so `cross_on_equal`
+    /// decides an order priced exactly at the touch) it is rejected with
+    /// `OrderError::WouldCross`, nothing trades and nothing rests.
+    pub fn place_post_only(&mut self, side: Side, price: i64, quantity: i64, id: u64) -> Result<(), OrderError> {
+        let mut order = NewOrder::limit(side, price, quantity, id);
+        order.post_only = true;
+        self.submit(order).map(|_| ())
+    }
+
+    /// Places an order with no price limit. It sweeps the opposite side from
+    /// the touch until filled or the side is empty; any remainder is
+    /// discarded rather than rested. Returns the trades, the unfilled
+    /// quantity and the slippage penalty for the sweep.
+    pub fn place_market_order(&mut self, side: Side, quantity: i64, id: u64) -> Result<MarketFill, OrderError> {
+        let trades = self.submit(NewOrder::market(side, quantity, id))?;
+
+        // Trades from stops the sweep triggered belong to other takers
+        let mut filled = 0;
+        let mut penalty: i128 = 0;
+        let mut levels_past_touch = 0;
+        let mut last_price = None;
+        for trade in trades.iter().filter(|t| t.taker_id == id) {
+            if last_price.is_some_and(|p| p != trade.price) {
+                levels_past_touch += 1;
+            }
+            last_price = Some(trade.price);
+            filled += trade.quantity;
+            let notional = trade.price as i128 * trade.quantity as i128;
+            penalty += notional * (self.config.market_slippage_bps_per_level * levels_past_touch) as i128 / 10_000;
+        }
+        Ok(MarketFill { trades, unfilled: quantity - filled, slippage_penalty: i64::try_from(penalty).unwrap_or(i64::MAX) })
+    }
+
+    /// Checks an order's price and quantity against the book's entry rules.
+    fn validate(&self, price: i64, quantity: i64) -> Result<(), OrderError> {
+        self.validate_quantity(quantity)?;
+        if price <= 0 {
+            return Err(OrderError::InvalidPrice(price));
+        }
+        if price.checked_mul(quantity).is_none() {
+            return Err(OrderError::NotionalOverflow { price, quantity });
+        }
+        let tick = self.config.tick_table.tick_for(price);
+        if price % tick != 0 {
+            return Err(OrderError::OffTick { price, tick });
+        }
+        Ok(())
+    }
+
+    /// With `lock_protection` on, rejects a resting `side` order at `price`
+    /// that would lock or cross the reference quote.
+    fn check_lock(&self, side: Side, price: i64) -> Result<(), OrderError> {
+        if !self.config.lock_protection {
+            return Ok(());
+        }
+        let (ref_bid, ref_ask) = self.reference_bbo;
+        let locked = match side {
+            Side::Buy => ref_ask.filter(|&ask| price >= ask),
+            Side::Sell => ref_bid.filter(|&bid| price <= bid),
+        };
+        match locked {
+            Some(quote) => Err(OrderError::WouldLock { price, quote }),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_quantity(&self, quantity: i64) -> Result<(), OrderError> {
+        if quantity <= 0 {
+            return Err(OrderError::InvalidQuantity(quantity));
+        }
+        if let Some(max) = self.config.max_order_quantity
+            && quantity > max
+        {
+            return Err(OrderError::QuantityTooLarge { quantity, max });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn submit(&mut self, order: NewOrder) -> Result<Vec<Trade>, OrderError> {
+        if order.market {
+            self.validate_quantity(order.quantity)?;
+        } else {
+            self.validate(order.price, order.quantity)?;
+        }
+        if self.contains(order.id) {
+            return Err(OrderError::DuplicateOrderId(order.id));
+        }
+
+        if self.state == TradingState::Halted {
+            return match self.config.halt_policy {
+                HaltPolicy::Reject => Err(OrderError::Halted),
+                HaltPolicy::Queue => {
+                    self.parked_orders.push_back(order);
+                    Ok(Vec::new())
+                }
+            };
+        }
+
+        if order.time_in_force == TimeInForce::Gtc && !order.market {
+            self.check_lock(order.side, order.price)?;
+        }
+
+        if order.post_only
+            && let Some(touch) = self.best_opposite_price(order.side)
+            && self.is_marketable(order.side, order.price, touch)
+        {
+            return Err(OrderError::WouldCross { price: order.price, touch });
+        }
+
+        self.replicate(|mirror| {
+            let _ = mirror.submit(order.clone());
+        });
+        let NewOrder { side, price, quantity, id, expire_after_events, expires_at, owner_id, time_in_force, .. } = order;
+
+        self.begin_event();
+        let now = self.clock.now();
+
+        if time_in_force == TimeInForce::Fok && self.fillable_quantity(side, price, quantity, owner_id, now) < quantity {
+            return Ok(Vec::new());
+        }
+
+        let mut remaining_qty = quantity;
+        let timestamp = self.next_timestamp;
+        self.next_timestamp += 1;
+
+        let mut trades = self.match_order(side, price, &mut remaining_qty, id, owner_id, now);
+
+        if remaining_qty > 0 && time_in_force == TimeInForce::Gtc {
+            let remaining_order = Order {
+                id,
+                side,
+                price,
+                quantity: remaining_qty,
+                timestamp,
+                created_at: now,
+                placed_event: self.event_count,
+                expire_after_events,
+                expires_at,
+                owner_id,
+                filled: 0,
+            };
+
+            if let Some(n) = expire_after_events {
+                self.event_expiries
+                    .entry(self.event_count + n)
+                    .or_default()
+                    .push(id);
+            }
+
+            self.levels_mut(side)
+                .entry(price)
+                .or_default()
+                .push_back(remaining_order);
+            self.order_index.insert(id, (side, price));
+            self.churn_log.push((now, side));
+            self.level_updates.insert((side, price), self.event_count);
+
+            let evicted = self.evict_excess_orders();
+            self.evicted.extend(evicted);
+        }
+
+        if self.config.record_bbo_history {
+            let bid = self.best_buy().map(|(p, _)| p);
+            let ask = self.best_sell().map(|(p, _)| p);
+            self.bbo_history.push((timestamp, bid, ask));
+            self.trade_log.extend(trades.iter().map(|t| (timestamp, t.price, t.aggressor)));
+        }
+
+        if !trades.is_empty() {
+            trades.extend(self.activate_stops());
+        }
+        Ok(trades)
+    }
+
+    /// Submits every pending stop whose trigger the last trade price has
+    /// reached, in the order the price move crossed them. Fills from
+    /// activated stops can trigger further stops.
+    fn activate_stops(&mut self) -> Vec<Trade> {
+        let Some(last) = self.last_trade_price else {
+            return Vec::new();
+        };
+        let (mut triggered, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.stop_orders)
+            .into_iter()
+            .partition(|(trigger, order)| match order.side {
+                Side::Buy => last >= *trigger,
+                Side::Sell => last <= *trigger,
+            });
+        self.stop_orders = pending;
+        // Buy stops are crossed by a rising price, lowest trigger first;
+        // sell stops by a falling one
+        triggered.sort_by_key(|(trigger, order)| match order.side {
+            Side::Buy => *trigger,
+            Side::Sell => -*trigger,
+        });
+
+        let mut trades = Vec::new();
+        for (_, order) in triggered {
+            // Stops were validated when placed; only a stop-limit that now
+            // locks the reference quote is refused, and it is dropped
+            if let Ok(fills) = self.submit(order) {
+                trades.extend(fills);
+            }
+        }
+        trades
+    }
+
+    /// Appends an already-accepted order to the back of its level without
+    /// matching, e.g. when rebuilding a book from saved state.
+    pub(crate) fn restore_order(&mut self, order: Order) {
+        if let Some(n) = order.expire_after_events {
+            self.event_expiries
+                .entry(order.placed_event + n)
+                .or_default()
+                .push(order.id);
+        }
+        let (side, price) = (order.side, order.price);
+        self.order_index.insert(order.id, (side, price));
+        self.levels_mut(side).entry(price).or_default().push_back(order);
+        self.level_updates.insert((side, price), self.event_count);
+    }
+
+    /// Counts a new book event and drops any orders whose event lifetime
+    /// has run out.
+    fn begin_event(&mut self) {
+        self.event_count += 1;
+        while let Some(entry) = self.event_expiries.first_entry() {
+            if *entry.key() > self.event_count {
+                break;
+            }
+            for id in entry.remove() {
+                self.remove_order(id);
+            }
+        }
+    }
+
+    /// Enforces `max_total_orders` by dropping orders from whichever side's
+    /// worst level is further from that side's touch, newest order first.
+    fn evict_excess_orders(&mut self) -> Vec<Order> {
+        let Some(max) = self.config.max_total_orders else {
+            return Vec::new();
+        };
+
+        let mut count = self.order_count();
+        let mut evicted = Vec::new();
+        while count > max {
+            // (distance of the worst level from the touch, worst price)
+            let bid = self.buy_levels.keys().next().zip(self.buy_levels.keys().next_back())
+                .map(|(&worst, &best)| (best - worst, worst));
+            let ask = self.sell_levels.keys().next_back().zip(self.sell_levels.keys().next())
+                .map(|(&worst, &best)| (worst - best, worst));
+            let (side, worst_price) = match (bid, ask) {
+                (Some((bid_gap, bid_worst)), Some((ask_gap, _))) if bid_gap >= ask_gap => (Side::Buy, bid_worst),
+                (_, Some((_, ask_worst))) => (Side::Sell, ask_worst),
+                (Some((_, bid_worst)), None) => (Side::Buy, bid_worst),
+                (None, None) => break,
+            };
+
+            let levels = self.levels_mut(side);
+            if let Some(orders) = levels.get_mut(&worst_price) {
+                evicted.extend(orders.pop_back());
+                if orders.is_empty() {
+                    levels.remove(&worst_price);
+                }
+            }
+            self.level_changed(side, worst_price);
+            count -= 1;
+        }
+        for order in &evicted {
+            self.order_index.remove(&order.id);
+        }
+        evicted
+    }
+
+    /// Stamps a level with the current event count, or forgets it once the
+    /// level is empty.
+    fn level_changed(&mut self, side: Side, price: i64) {
+        if self.levels(side).contains_key(&price) {
+            self.level_updates.insert((side, price), self.event_count);
+        } else {
+            self.level_updates.remove(&(side, price));
+        }
+    }
+
+    /// Finds a resting order's (side, price, queue index) through the id
+    /// index, scanning only its own level.
+    fn locate_order(&self, id: u64) -> Option<(Side, i64, usize)> {
+        let &(side, price) = self.order_index.get(&id)?;
+        let index = self.levels(side).get(&price)?.iter().position(|o| o.id == id)?;
+        Some((side, price, index))
+    }
+
+    /// Removes a resting order by id.
+    fn remove_order(&mut self, id: u64) -> Option<Order> {
+        let (side, price, index) = self.locate_order(id)?;
+        let levels = self.levels_mut(side);
+        let orders = levels.get_mut(&price)?;
+        let order = orders.remove(index);
+        if orders.is_empty() {
+            levels.remove(&price);
+        }
+        self.order_index.remove(&id);
+        self.level_changed(side, price);
+        order
+    }
+
+    /// Replaces a resting order's price and quantity. The replacement is
+    /// treated as a fresh order (new time priority, may trade immediately),
+    /// except that with `keep_priority_on_downsize` a pure quantity
+    /// reduction at the same price is applied in place and keeps its queue
+    /// position.
+    pub fn cancel_replace(&mut self, id: u64, new_price: i64, new_quantity: i64) -> Result<Vec<Trade>, OrderError> {
+        self.validate(new_price, new_quantity)?;
+        if self.state == TradingState::Halted {
+            return Err(OrderError::Halted);
+        }
+
+        // The mirror replays the whole replace itself rather than its legs
+        let mirror = self.mirror.take();
+        let result = self.replace_order(id, new_price, new_quantity);
+        self.mirror = mirror;
+        self.replicate(|mirror| {
+            let _ = mirror.cancel_replace(id, new_price, new_quantity);
+        });
+        result
+    }
+
+    fn replace_order(&mut self, id: u64, new_price: i64, new_quantity: i64) -> Result<Vec<Trade>, OrderError> {
+        let (side, price, index) = self.locate_order(id).ok_or(OrderError::UnknownOrder(id))?;
+        let resting_qty = self.levels(side)[&price][index].quantity;
+        if self.config.keep_priority_on_downsize && new_price == price && new_quantity < resting_qty {
+            return self.amend_quantity(id, new_quantity).map(|()| Vec::new());
+        }
+        // Reject before the cancel leg so a refused replace leaves the order
+        self.check_lock(side, new_price)?;
+
+        let old = self.remove_order(id).ok_or(OrderError::UnknownOrder(id))?;
+        let mut replacement = NewOrder::limit(side, new_price, new_quantity, id);
+        replacement.expire_after_events = old.expire_after_events;
+        replacement.expires_at = old.expires_at;
+        replacement.owner_id = old.owner_id;
+        let trades = self.submit(replacement)?;
+        self.churn_log.push((self.clock.now(), side));
+        Ok(trades)
+    }
+
+    /// Changes a resting order's quantity in place. A decrease keeps the
+    /// order's queue position; an increase sends it to the back of its level.
+    /// `new_quantity` must be positive: use `cancel_order` to pull an order.
+    pub fn amend_quantity(&mut self, id: u64, new_quantity: i64) -> Result<(), OrderError> {
+        let (_, price, _) = self.locate_order(id).ok_or(OrderError::UnknownOrder(id))?;
+        self.validate(price, new_quantity)?;
+        self.replicate(|mirror| {
+            let _ = mirror.amend_quantity(id, new_quantity);
+        });
+
+        self.begin_event();
+        // The event may have expired the order itself
+        let (side, price, index) = self.locate_order(id).ok_or(OrderError::UnknownOrder(id))?;
+        let timestamp = self.next_timestamp;
+        let orders = self.levels_mut(side).get_mut(&price).expect("located order's level exists");
+        if new_quantity > orders[index].quantity {
+            let mut order = orders.remove(index).expect("located order exists");
+            order.quantity = new_quantity;
+            order.timestamp = timestamp;
+            orders.push_back(order);
+            self.next_timestamp += 1;
+        } else {
+            orders[index].quantity = new_quantity;
+        }
+        self.level_changed(side, price);
+        Ok(())
+    }
+
+    /// Cancels a resting order, returning it, or `None` if `id` is not on the
+    /// book. Empty levels are dropped.
+    pub fn cancel_order(&mut self, id: u64) -> Option<Order> {
+        self.locate_order(id)?;
+        self.replicate(|mirror| {
+            mirror.cancel_order(id);
+        });
+        self.begin_event();
+        let order = self.remove_order(id)?;
+        self.churn_log.push((self.clock.now(), order.side));
+        Some(order)
+    }
+
+    /// Pulls up to `reduce_by` from the level at `price`, oldest orders first.
+    /// Orders reduced to zero are cancelled. Returns the quantity removed.
+    pub fn reduce_level(&mut self, side: Side, price: i64, reduce_by: i64) -> i64 {
+        if !self.levels(side).contains_key(&price) {
+            return 0;
+        }
+        self.replicate(|mirror| {
+            mirror.reduce_level(side, price, reduce_by);
+        });
+        self.begin_event();
+
+        let levels = self.levels_mut(side);
+        let Some(orders) = levels.get_mut(&price) else {
+            return 0;
+        };
+
+        let mut removed = 0;
+        let mut cancelled = Vec::new();
+        while removed < reduce_by {
+            let Some(front) = orders.front_mut() else {
+                break;
+            };
+            let take = std::cmp::min(reduce_by - removed, front.quantity);
+            front.quantity -= take;
+            removed += take;
+            if front.quantity == 0 {
+                cancelled.extend(orders.pop_front().map(|o| o.id));
+            }
+        }
+
+        if orders.is_empty() {
+            levels.remove(&price);
+        }
+        for id in cancelled {
+            self.order_index.remove(&id);
+        }
+        self.level_changed(side, price);
+        removed
+    }
+
+    /// Collapses every level past the best `keep_levels` on each side into
+    /// one synthetic order (`COMPACTED_BID_ID` / `COMPACTED_ASK_ID`, owner 0)
+    /// holding their total quantity, priced at the nearest compacted level.
+    ///
+    /// This is lossy: deeper liquidity is moved up to that price, so a sweep
+    /// past the kept levels fills better than it would have, and the
+    /// individual orders' ids, owners, queue priority and expiries are gone.
+    pub fn compact_tail(&mut self, keep_levels: usize) {
+        self.replicate(|mirror| mirror.compact_tail(keep_levels));
+        for side in [Side::Buy, Side::Sell] {
+            let tail: Vec<i64> = self.levels_from_touch(side).skip(keep_levels).map(|(&p, _)| p).collect();
+            let Some(&boundary) = tail.first() else {
+                continue;
+            };
+
+            let mut quantity: i64 = 0;
+            for price in tail {
+                let orders = self.levels_mut(side).remove(&price).unwrap_or_default();
+                for order in orders {
+                    quantity = quantity.saturating_add(order.quantity);
+                    self.order_index.remove(&order.id);
+                }
+                self.level_changed(side, price);
+            }
+
+            let id = match side {
+                Side::Buy => COMPACTED_BID_ID,
+                Side::Sell => COMPACTED_ASK_ID,
+            };
+            let synthetic = Order {
+                id,
+                side,
+                price: boundary,
+                quantity,
+                timestamp: self.next_timestamp,
+                created_at: self.clock.now(),
+                placed_event: self.event_count,
+                expire_after_events: None,
+                expires_at: None,
+                owner_id: 0,
+                filled: 0,
+            };
+            self.next_timestamp += 1;
+            self.levels_mut(side).entry(boundary).or_default().push_back(synthetic);
+            self.order_index.insert(id, (side, boundary));
+            self.level_changed(side, boundary);
+        }
+    }
+
+    fn match_order(
+        &mut self,
+        side: Side,
+        price: i64,
+        remaining_qty: &mut i64,
+        taker_id: u64,
+        taker_owner: u64,
+        now: u64,
+    ) -> Vec<Trade> {
+        let mut fills = Fills {
+            taker_id,
+            taker_owner,
+            aggressor: side,
+            stp_owner: self.stp_owner(taker_owner),
+            trades: Vec::new(),
+            removed: Vec::new(),
+            trace: self.config.trace.then(Vec::new),
+            now,
+            rested_millis_qty: 0,
+            completed: Vec::new(),
+        };
+        let policy = self.config.matching_policy;
+        let lot_size = self.lot_size();
+
+        while *remaining_qty >= lot_size {
+            let best_price = match self.best_opposite_price(side) {
+                Some(p) => p,
+                None => break,
+            };
+
+            if !self.is_marketable(side, price, best_price) {
+                break;
+            }
+
+            if self.breaches_price_band(best_price) {
+                let cancelled = std::mem::take(remaining_qty);
+                fills.note(|| format!("band breach at {}: cancel {} remaining", best_price, cancelled));
+                break;
+            }
+
+            fills.note(|| format!("select {} level {}", side.opposite(), best_price));
+
+            let before_qty = *remaining_qty;
+            let levels = self.levels_mut(side.opposite());
+            let mut level_empty = false;
+            if let Some(orders) = levels.get_mut(&best_price) {
+                fills.drop_expired(orders);
+                if !orders.is_empty() {
+                    match policy {
+                        MatchingPolicy::PriceTime => fills.price_time(orders, remaining_qty, lot_size),
+                        MatchingPolicy::Hybrid { top_order_fraction } => {
+                            fills.hybrid(orders, remaining_qty, top_order_fraction)
+                        }
+                        MatchingPolicy::ProRata => fills.hybrid(orders, remaining_qty, 0.0),
+                    }
+                }
+
+                level_empty = orders.is_empty();
+            }
+
+            if level_empty {
+                levels.remove(&best_price);
+                fills.note(|| format!("remove {} level {}", side.opposite(), best_price));
+            }
+            self.level_changed(side.opposite(), best_price);
+            if *remaining_qty < before_qty {
+                self.last_trade_price = Some(best_price);
+            } else if !level_empty {
+                // Only odd lots rest at the touch
+                break;
+            }
+        }
+
+        for id in &fills.removed {
+            self.order_index.remove(id);
+        }
+        self.time_in_book.0 += fills.rested_millis_qty;
+        self.time_in_book.1 += fills.trades.iter().map(|t| t.quantity as i128).sum::<i128>();
+        self.trace_log.extend(fills.trace.unwrap_or_default());
+        for trade in &fills.trades {
+            if let Some(on_trade) = self.on_trade.0.as_mut() {
+                on_trade(trade);
+            }
+            self.stats.record(trade);
+            let notional = trade.price as i128 * trade.quantity as i128;
+            *self.account_notional.entry(trade.taker_owner).or_default() += notional;
+            if trade.maker_owner != trade.taker_owner {
+                *self.account_notional.entry(trade.maker_owner).or_default() += notional;
+            }
+            let maker = &mut self.account_volume.entry(trade.maker_owner).or_default().0;
+            *maker = maker.saturating_add(trade.quantity);
+            let taker = &mut self.account_volume.entry(trade.taker_owner).or_default().1;
+            *taker = taker.saturating_add(trade.quantity);
+        }
+        if let Some(on_order_filled) = self.on_order_filled.0.as_mut() {
+            for &(id, price, filled) in &fills.completed {
+                on_order_filled(id, price, filled);
+            }
+        }
+        fills.trades
+    }
+
+    fn breaches_price_band(&self, price: i64) -> bool {
+        self.breaches_band_from(self.last_trade_price, price)
+    }
+
+    fn breaches_band_from(&self, last_trade_price: Option<i64>, price: i64) -> bool {
+        let (Some(band_bps), Some(last)) = (self.config.trade_price_band_bps, last_trade_price) else {
+            return false;
+        };
+        (price - last).abs() as f64 * 10_000.0 / last as f64 > band_bps
+    }
+
+    /// Granularity price-time fills are rounded down to; 1 under the other
+    /// matching policies.
+    fn lot_size(&self) -> i64 {
+        match (self.config.matching_policy, self.config.odd_lot_policy) {
+            // `config_mut` can skip the check in `with_config`
+            (MatchingPolicy::PriceTime, OddLotPolicy::RoundLotsOnly { lot_size }) => lot_size.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Owner whose resting orders an order from `taker_owner` must not trade
+    /// with, if self-trade prevention applies to it.
+    fn stp_owner(&self, taker_owner: u64) -> Option<u64> {
+        match self.config.self_trade_prevention {
+            SelfTradePrevention::Off => None,
+            SelfTradePrevention::CancelResting => (taker_owner != 0).then_some(taker_owner),
+        }
+    }
+
+    /// How much of a `side` order from `owner_id` limited at `price` would
+    /// fill right now, capped at `quantity`, following the same stopping
+    /// and lot rounding rules as matching.
+    fn fillable_quantity(&self, side: Side, price: i64, quantity: i64, owner_id: u64, now: u64) -> i64 {
+        let stp_owner = self.stp_owner(owner_id);
+        let lot_size = self.lot_size();
+        let mut remaining = quantity;
+        let mut last_trade_price = self.last_trade_price;
+        for (&level_price, orders) in self.levels_from_touch(side.opposite()) {
+            if remaining < lot_size
+                || !self.is_marketable(side, price, level_price)
+                || self.breaches_band_from(last_trade_price, level_price)
+            {
+                break;
+            }
+            let before = remaining;
+            let mut eligible = orders
+                .iter()
+                .filter(|o| !o.is_expired(now) && stp_owner != Some(o.owner_id))
+                .peekable();
+            let any_eligible = eligible.peek().is_some();
+            for o in eligible {
+                remaining -= std::cmp::min(remaining, o.quantity) / lot_size * lot_size;
+                if remaining < lot_size {
+                    break;
+                }
+            }
+            if remaining == before {
+                if any_eligible {
+                    // Only odd lots rest at the touch
+                    break;
+                }
+                continue;
+            }
+            last_trade_price = Some(level_price);
+        }
+        quantity - remaining
+    }
+
+    fn best_opposite_price(&self, side: Side) -> Option<i64> {
+        match side {
+            Side::Buy => self.sell_levels.keys().next().copied(),
+            Side::Sell => self.buy_levels.keys().next_back().copied(),
+        }
+    }
+
+    fn is_marketable(&self, side: Side, price: i64, opposite_price: i64) -> bool {
+        if price == opposite_price {
+            return self.config.cross_on_equal;
+        }
+        match side {
+            Side::Buy => price > opposite_price,
+            Side::Sell => price < opposite_price,
+        }
+    }
+
+    pub(crate) fn levels(&self, side: Side) -> &BTreeMap<i64, VecDeque<Order>> {
+        match side {
+            Side::Buy => &self.buy_levels,
+            Side::Sell => &self.sell_levels,
+        }
+    }
+
+    /// Levels on one side, best price first.
+    pub(crate) fn levels_from_touch(&self, side: Side) -> Box<dyn Iterator<Item = (&i64, &VecDeque<Order>)> + '_> {
+        match side {
+            Side::Buy => Box::new(self.buy_levels.iter().rev()),
+            Side::Sell => Box::new(self.sell_levels.iter()),
+        }
+    }
+
+    pub(crate) fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<i64, VecDeque<Order>> {
+        match side {
+            Side::Buy => &mut self.buy_levels,
+            Side::Sell => &mut self.sell_levels,
+        }
+    }
+
+    pub fn best_buy(&self) -> Option<(i64, i64)> {
+        self.buy_levels
+            .iter()
+            .next_back()
+            .map(|(price, orders)| (*price, level_quantity(orders)))
+    }
+
+    pub fn best_sell(&self) -> Option<(i64, i64)> {
+        self.sell_levels
+            .iter()
+            .next()
+            .map(|(price, orders)| (*price, level_quantity(orders)))
+    }
+
+    /// Aggregated price levels with order counts, best first, up to `depth`
+    /// per side.
+    pub fn ladder(&self, depth: usize) -> Ladder {
+        let side_levels = |side| {
+            self.levels_from_touch(side)
+                .take(depth)
+                .map(|(&price, orders)| LadderLevel {
+                    price,
+                    quantity: level_quantity(orders),
+                    order_count: orders.len(),
+                })
+                .collect()
+        };
+        Ladder { bids: side_levels(Side::Buy), asks: side_levels(Side::Sell) }
+    }
+
+    /// Aggregated price levels, best first, up to `depth` per side.
+    pub fn depth_snapshot(&self, depth: usize) -> DepthSnapshot {
+        let ladder = self.ladder(depth);
+        let pairs = |levels: Vec<LadderLevel>| levels.into_iter().map(|l| (l.price, l.quantity)).collect();
+        let bids: Vec<(i64, i64)> = pairs(ladder.bids);
+        let asks: Vec<(i64, i64)> = pairs(ladder.asks);
+        let spread = match (bids.first(), asks.first()) {
+            (Some(&(bid, _)), Some(&(ask, _))) => Some(ask - bid),
+            _ => None,
+        };
+        DepthSnapshot { symbol: self.symbol.clone(), bids, asks, spread }
+    }
+
+    pub fn display_book(&self, depth: usize) {
+        println!("\n🚀 {} ORDER BOOK", self.symbol);
+        println!("═══════════════════════════════════════");
+        for line in self.ladder_lines(depth) {
+            println!("   {}", line);
+        }
+        println!("═══════════════════════════════════════");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valcoin_pricing() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+
+        // Test Valhalla prices around $1000
+        let trades = book.place_order(Side::Buy, 100000, 100000, 1).unwrap(); // $1000, 10.0 Valhalla
+        assert!(trades.is_empty());
+
+        let trades = book.place_order(Side::Sell, 100000, 50000, 2).unwrap(); // $1000, 5.0 Valhalla
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].price, 100000); // $1000.00
+    }
+
+    #[test]
+    fn test_depth_snapshot() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let seed = [
+            (Side::Buy, 99500, 10000),
+            (Side::Buy, 99500, 5000),
+            (Side::Buy, 99000, 20000),
+            (Side::Buy, 98500, 30000),
+            (Side::Sell, 100500, 10000),
+            (Side::Sell, 101000, 15000),
+            (Side::Sell, 101500, 20000),
+        ];
+        for (id, (side, price, qty)) in (1..).zip(seed) {
+            book.place_order(side, price, qty, id).unwrap();
+        }
+
+        let snapshot = book.depth_snapshot(2);
+        assert_eq!(snapshot.symbol, "Valhalla/USD");
+        assert_eq!(snapshot.bids.first().copied(), book.best_buy());
+        assert_eq!(snapshot.asks.first().copied(), book.best_sell());
+        assert_eq!(snapshot.bids, vec![(99500, 15000), (99000, 20000)]);
+        assert_eq!(snapshot.asks, vec![(100500, 10000), (101000, 15000)]);
+        assert_eq!(snapshot.spread, Some(1000));
+
+        assert_eq!(OrderBook::new("X".to_string()).depth_snapshot(5).spread, None);
+    }
+
+    #[test]
+    fn test_ladder_order_counts() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 5000, 2).unwrap();
+        book.place_order(Side::Buy, 99500, 5000, 3).unwrap();
+        book.place_order(Side::Buy, 99000, 20000, 4).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 5).unwrap();
+
+        let ladder = book.ladder(5);
+        assert_eq!(ladder.bids, vec![
+            LadderLevel { price: 99500, quantity: 20000, order_count: 3 },
+            LadderLevel { price: 99000, quantity: 20000, order_count: 1 },
+        ]);
+        assert_eq!(ladder.asks, vec![LadderLevel { price: 100500, quantity: 10000, order_count: 1 }]);
+        assert_eq!(book.ladder(1).bids.len(), 1);
+    }
+
+    #[test]
+    fn test_trade_roles() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+
+        let trades = book.place_order(Side::Sell, 99500, 10000, 2).unwrap();
+        assert_eq!((trades[0].maker_id, trades[0].maker_side), (1, Side::Buy));
+        assert_eq!((trades[0].taker_id, trades[0].aggressor), (2, Side::Sell));
+    }
+
+    #[test]
+    fn test_order_timestamps_from_clock() {
+        let clock = crate::clock::MockClock::new(1_700_000_000_000);
+        let mut book = OrderBook::with_clock("Valhalla/USD".to_string(), Arc::new(clock.clone()));
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        clock.advance(250);
+        book.place_order(Side::Buy, 99500, 10000, 2).unwrap();
+
+        let (first, second) = (book.get_order(1).unwrap(), book.get_order(2).unwrap());
+        assert_eq!((first.created_at, second.created_at), (1_700_000_000_000, 1_700_000_000_250));
+        // Priority still comes from the sequence counter
+        assert_eq!((first.timestamp, second.timestamp), (1, 2));
+    }
+
+    #[test]
+    fn test_expired_order_does_not_match() {
+        let clock = crate::clock::MockClock::new(1_000);
+        let mut book = OrderBook::with_clock("Valhalla/USD".to_string(), Arc::new(clock.clone()));
+        book.place_order_until(Side::Sell, 100000, 10000, 1, 2_000).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+
+        clock.advance(1_000);
+        let trades = book.place_order(Side::Buy, 100500, 10000, 3).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].maker_id, 2);
+        assert!(!book.contains(1));
+        assert_eq!(book.best_sell(), None);
+    }
+
+    #[test]
+    fn test_expire_orders_sweep() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order_until(Side::Buy, 99500, 10000, 1, 5_000).unwrap();
+        book.place_order_until(Side::Buy, 99000, 10000, 2, 9_000).unwrap();
+        book.place_order_until(Side::Sell, 100500, 10000, 3, 5_000).unwrap();
+        book.place_order(Side::Sell, 100500, 20000, 4).unwrap();
+
+        assert!(book.expire_orders(4_999).is_empty());
+        let expired: Vec<u64> = book.expire_orders(5_000).iter().map(|o| o.id).collect();
+        assert_eq!(expired, vec![1, 3]);
+        assert_eq!(book.best_buy(), Some((99000, 10000)));
+        assert_eq!(book.best_sell(), Some((100500, 20000)));
+    }
+
+    #[test]
+    fn test_mirror_tracks_primary() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.attach_mirror(OrderBook::new("Valhalla/USD shadow".to_string()));
+
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 20000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 30000, 3).unwrap();
+        book.place_order(Side::Sell, 99500, 5000, 4).unwrap();
+        book.cancel_order(2);
+        book.cancel_replace(3, 101000, 15000).unwrap();
+        book.amend_quantity(1, 2000).unwrap();
+        book.reduce_level(Side::Sell, 101000, 5000);
+
+        let mirror = book.mirror().unwrap();
+        assert_eq!(mirror.depth_snapshot(10).bids, book.depth_snapshot(10).bids);
+        assert_eq!(mirror.depth_snapshot(10).asks, book.depth_snapshot(10).asks);
+        assert_eq!(book.mirror_in_sync(), Some(true));
+
+        // A change made behind the primary's back shows up as divergence
+        let mut mirror = book.detach_mirror().unwrap();
+        mirror.cancel_order(1);
+        book.attach_mirror(mirror);
+        assert_eq!(book.mirror_in_sync(), Some(false));
+    }
+
+    #[test]
+    fn test_post_only() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        book.place_post_only(Side::Buy, 100000, 10000, 2).unwrap();
+        assert_eq!(book.best_buy(), Some((100000, 10000)));
+
+        // Exactly at the touch crosses by default
+        assert_eq!(
+            book.place_post_only(Side::Buy, 100500, 10000, 3),
+            Err(OrderError::WouldCross { price: 100500, touch: 100500 })
+        );
+        assert!(!book.contains(3));
+        assert_eq!(book.best_sell(), Some((100500, 10000)));
+        assert_eq!(book.stats().trade_count, 0);
+
+        book.config_mut().cross_on_equal = false;
+        book.place_post_only(Side::Buy, 100500, 10000, 4).unwrap();
+        assert_eq!(book.best_buy(), Some((100500, 10000)));
+    }
+
+    #[test]
+    fn test_resting_id_cannot_be_reused() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 150, 10, 1).unwrap();
+        assert_eq!(book.place_order(Side::Buy, 140, 10, 1), Err(OrderError::DuplicateOrderId(1)));
+        assert_eq!(book.order_count(), 1);
+
+        // The index still reaches the original, and the id frees up once it's gone
+        assert_eq!(book.cancel_order(1).map(|o| o.price), Some(150));
+        assert_eq!(book.order_count(), 0);
+        book.place_order(Side::Buy, 140, 10, 1).unwrap();
+        assert_eq!(book.best_buy(), Some((140, 10)));
+    }
+
+    #[test]
+    fn test_place_batch_is_all_or_nothing() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        let before = format!("{:?}", book.depth_snapshot(10));
+
+        let bad_quantity = [(Side::Buy, 99000, 10000, 3), (Side::Sell, 99500, 0, 4)];
+        assert_eq!(book.place_batch(&bad_quantity), Err(OrderError::InvalidQuantity(0)));
+        let repeated_id = [(Side::Buy, 99000, 10000, 3), (Side::Sell, 101000, 10000, 3)];
+        assert_eq!(book.place_batch(&repeated_id), Err(OrderError::DuplicateOrderId(3)));
+        let resting_id = [(Side::Buy, 99000, 10000, 3), (Side::Sell, 101000, 10000, 2)];
+        assert_eq!(book.place_batch(&resting_id), Err(OrderError::DuplicateOrderId(2)));
+        assert_eq!(format!("{:?}", book.depth_snapshot(10)), before);
+        assert_eq!(book.sequence(), 2);
+
+        // Later orders in a batch can trade against earlier ones
+        let trades = book.place_batch(&[(Side::Sell, 99000, 20000, 5), (Side::Buy, 99000, 5000, 6)]).unwrap();
+        let fills: Vec<(u64, u64, i64)> = trades.iter().map(|t| (t.maker_id, t.taker_id, t.quantity)).collect();
+        assert_eq!(fills, vec![(1, 5, 10000), (5, 6, 5000)]);
+    }
+
+    #[test]
+    fn test_export_import_state() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 20000, 2).unwrap();
+        book.place_order(Side::Buy, 100500, 5000, 3).unwrap();
+        book.place_stop_order(Side::Sell, 99000, None, 10000, 4).unwrap();
+        let saved = book.export_state();
+        let before = (book.depth_snapshot(10), book.stats().clone(), book.sequence());
+
+        book.place_order(Side::Sell, 99500, 10000, 5).unwrap();
+        book.place_order(Side::Buy, 101000, 30000, 6).unwrap();
+        book.cancel_order(1);
+        assert_ne!(book.depth_snapshot(10), before.0);
+
+        book.import_state(saved);
+        assert_eq!((book.depth_snapshot(10), book.stats().clone(), book.sequence()), before);
+        assert_eq!(book.session_high(), Some(100500));
+        assert_eq!(book.pending_stop_count(), 1);
+        assert!(book.contains(1) && !book.contains(5));
+
+        // Matching carries on from the restored queue
+        let trades = book.place_order(Side::Buy, 100500, 15000, 7).unwrap();
+        assert_eq!((trades[0].maker_id, trades[0].quantity), (2, 15000));
+    }
+
+    #[test]
+    fn test_compact_tail() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        for (id, price) in (1..).zip([100500, 101000, 101500, 102000, 102500]) {
+            book.place_order(Side::Sell, price, 10000, id).unwrap();
+        }
+        book.place_order(Side::Sell, 102000, 5000, 6).unwrap();
+        book.place_order(Side::Buy, 99500, 10000, 7).unwrap();
+        book.place_order(Side::Buy, 99000, 20000, 8).unwrap();
+
+        book.compact_tail(2);
+        let snapshot = book.depth_snapshot(10);
+        assert_eq!(snapshot.asks, vec![(100500, 10000), (101000, 10000), (101500, 35000)]);
+        assert_eq!(snapshot.bids, vec![(99500, 10000), (99000, 20000)]);
+        assert!(book.contains(1) && book.contains(2));
+        assert!(!book.contains(4) && !book.contains(6));
+        assert_eq!(book.get_order(COMPACTED_ASK_ID).map(|o| o.quantity), Some(35000));
+        assert!(!book.contains(COMPACTED_BID_ID));
+
+        // The synthetic order trades like any other
+        let trades = book.place_order(Side::Buy, 101500, 40000, 9).unwrap();
+        assert_eq!(trades.last().map(|t| (t.maker_id, t.quantity)), Some((COMPACTED_ASK_ID, 20000)));
+    }
+
+    #[test]
+    fn test_lock_protection() {
+        let config = BookConfig { lock_protection: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.set_reference_bbo(Some(99800), Some(100200));
+
+        assert_eq!(book.place_order(Side::Buy, 100200, 10000, 1), Err(OrderError::WouldLock { price: 100200, quote: 100200 }));
+        assert_eq!(book.place_order(Side::Sell, 99700, 10000, 2), Err(OrderError::WouldLock { price: 99700, quote: 99800 }));
+        assert_eq!(book.order_count(), 0);
+
+        book.place_order(Side::Buy, 100100, 10000, 3).unwrap();
+        assert_eq!(book.best_buy(), Some((100100, 10000)));
+        // IOC never rests, so it may be priced through the protected quote
+        let trades = book.place_order_tif(Side::Sell, 99700, 5000, 5, TimeInForce::Ioc).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(book.best_buy(), Some((100100, 5000)));
+    }
+
+    #[test]
+    fn test_lock_protection_in_batches_and_stops() {
+        let config = BookConfig { lock_protection: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.set_reference_bbo(Some(100), Some(200));
+
+        // A locking order mid-batch rejects the whole batch
+        let batch = [(Side::Buy, 150, 10, 1), (Side::Buy, 200, 10, 2), (Side::Sell, 250, 10, 3)];
+        assert_eq!(book.place_batch(&batch), Err(OrderError::WouldLock { price: 200, quote: 200 }));
+        assert_eq!(book.order_count(), 0);
+
+        assert_eq!(
+            book.place_stop_order(Side::Buy, 180, Some(200), 10, 4),
+            Err(OrderError::WouldLock { price: 200, quote: 200 })
+        );
+        assert_eq!(book.pending_stop_count(), 0);
+    }
+
+    #[test]
+    fn test_rejected_replace_keeps_order() {
+        let config = BookConfig { lock_protection: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.set_reference_bbo(Some(100), Some(200));
+        book.place_order(Side::Buy, 150, 10, 1).unwrap();
+
+        assert_eq!(book.cancel_replace(1, 200, 10), Err(OrderError::WouldLock { price: 200, quote: 200 }));
+        assert_eq!(book.get_order(1).map(|o| (o.price, o.quantity)), Some((150, 10)));
+        assert_eq!(book.best_buy(), Some((150, 10)));
+    }
+
+    #[test]
+    fn test_on_order_filled_fires_for_full_fills_only() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        book.set_on_order_filled(Box::new(move |id, price, filled| sink.lock().unwrap().push((id, price, filled))));
+
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 2).unwrap();
+        book.place_order(Side::Sell, 101500, 10000, 3).unwrap();
+        book.place_order(Side::Buy, 100500, 4000, 4).unwrap();
+        assert!(events.lock().unwrap().is_empty());
+
+        // Finishes order 1, fills 2 completely, leaves 3 partially filled
+        book.place_order(Side::Buy, 101500, 31000, 5).unwrap();
+        assert_eq!(*events.lock().unwrap(), vec![(1, 100500, 10000), (2, 101000, 20000)]);
+        assert_eq!(book.get_order(3).map(|o| (o.quantity, o.filled)), Some((5000, 5000)));
+    }
+
+    #[test]
+    fn test_equal_price_crosses_by_default() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        let trades = book.place_order(Side::Buy, 100500, 10000, 2).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(book.best_sell(), None);
+        assert_eq!(book.best_buy(), None);
+    }
+
+    #[test]
+    fn test_equal_price_rests_without_cross_on_equal() {
+        let config = BookConfig { cross_on_equal: false, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        let trades = book.place_order(Side::Buy, 100500, 10000, 2).unwrap();
+        assert!(trades.is_empty());
+        assert_eq!(book.best_buy(), Some((100500, 10000)));
+        assert_eq!(book.best_sell(), Some((100500, 10000)));
+
+        // A strictly better price still crosses
+        let trades = book.place_order(Side::Sell, 100400, 10000, 3).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].maker_id, 2);
+    }
+
+    #[test]
+    fn test_reduce_level_spills_into_next_order() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 30000, 2).unwrap();
+        book.place_order(Side::Buy, 99000, 50000, 3).unwrap();
+
+        assert_eq!(book.reduce_level(Side::Buy, 99500, 25000), 25000);
+        let level = &book.buy_levels[&99500];
+        assert_eq!(level.len(), 1);
+        assert_eq!((level[0].id, level[0].quantity), (2, 15000));
+
+        // Asking for more than is resting removes the whole level
+        assert_eq!(book.reduce_level(Side::Buy, 99500, 100000), 15000);
+        assert_eq!(book.best_buy(), Some((99000, 50000)));
+        assert_eq!(book.reduce_level(Side::Sell, 100500, 10000), 0);
+    }
+
+    #[test]
+    fn test_order_expires_after_events() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order_expiring(Side::Buy, 99500, 10000, 1, 2).unwrap();
+
+        // First event after placement: still resting
+        book.place_order(Side::Buy, 99000, 10000, 2).unwrap();
+        assert_eq!(book.best_buy(), Some((99500, 10000)));
+
+        // Second event: order 1 is gone before it can trade
+        let trades = book.place_order(Side::Sell, 99500, 10000, 3).unwrap();
+        assert!(trades.is_empty());
+        assert_eq!(book.best_buy(), Some((99000, 10000)));
+        assert_eq!(book.best_sell(), Some((99500, 10000)));
+    }
+
+    #[test]
+    fn test_halt_rejects_by_default() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.halt();
+        assert_eq!(book.place_order(Side::Buy, 99500, 10000, 1), Err(OrderError::Halted));
+        book.resume();
+        assert!(book.place_order(Side::Buy, 99500, 10000, 1).is_ok());
+    }
+
+    #[test]
+    fn test_halt_queue_releases_on_resume() {
+        let config = BookConfig { halt_policy: HaltPolicy::Queue, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        book.halt();
+        assert_eq!(book.state(), TradingState::Halted);
+        assert!(book.place_order(Side::Buy, 100500, 4000, 2).unwrap().is_empty());
+        assert!(book.place_order(Side::Buy, 100500, 8000, 3).unwrap().is_empty());
+        // Nothing trades or rests while halted
+        assert_eq!(book.best_sell(), Some((100500, 10000)));
+        assert_eq!(book.best_buy(), None);
+
+        let trades = book.resume();
+        assert_eq!(book.state(), TradingState::Continuous);
+        assert_eq!(trades.len(), 2);
+        assert_eq!((trades[0].taker_id, trades[0].quantity), (2, 4000));
+        assert_eq!((trades[1].taker_id, trades[1].quantity), (3, 6000));
+        assert_eq!(book.best_buy(), Some((100500, 2000)));
+    }
+
+    #[test]
+    fn test_trace_two_level_sweep() {
+        let config = BookConfig { trace: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 2).unwrap();
+        assert!(book.take_trace().is_empty());
+
+        book.place_order(Side::Buy, 101000, 15000, 3).unwrap();
+        assert_eq!(book.take_trace(), vec![
+            "select SELL level 100500",
+            "pop order #1 (10000 resting)",
+            "trade 10000 @ 100500 (maker #1, taker #3)",
+            "remove SELL level 100500",
+            "select SELL level 101000",
+            "pop order #2 (20000 resting)",
+            "trade 5000 @ 101000 (maker #2, taker #3)",
+        ]);
+        assert!(book.take_trace().is_empty());
+    }
+
+    #[test]
+    fn test_level_timestamps_track_changes() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        assert_eq!(book.level_timestamps(Side::Buy), vec![(99500, 1), (99000, 2)]);
+
+        // A partial fill at the touch only restamps that level
+        book.place_order(Side::Sell, 99500, 4000, 4).unwrap();
+        assert_eq!(book.level_timestamps(Side::Buy), vec![(99500, 4), (99000, 2)]);
+        assert_eq!(book.level_timestamps(Side::Sell), vec![(100500, 3)]);
+
+        book.reduce_level(Side::Buy, 99000, 1000);
+        assert_eq!(book.level_timestamps(Side::Buy), vec![(99500, 4), (99000, 5)]);
+
+        book.reduce_level(Side::Buy, 99000, 9000);
+        assert_eq!(book.level_timestamps(Side::Buy), vec![(99500, 4)]);
+    }
+
+    #[test]
+    fn test_max_total_orders_evicts_furthest_from_touch() {
+        let config = BookConfig { max_total_orders: Some(4), ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 97000, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 4).unwrap();
+        assert!(book.take_evicted().is_empty());
+
+        // Fifth order: the $25 deep bid is the furthest from its touch
+        book.place_order(Side::Sell, 100500, 10000, 5).unwrap();
+        let evicted = book.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!((evicted[0].id, evicted[0].price), (2, 97000));
+
+        // Next the ask ladder is deepest; the near-touch orders all remain
+        book.place_order(Side::Buy, 99400, 10000, 6).unwrap();
+        let evicted = book.take_evicted();
+        assert_eq!(evicted.iter().map(|o| o.id).collect::<Vec<_>>(), vec![4]);
+        assert_eq!(book.order_count(), 4);
+        assert_eq!(book.best_buy(), Some((99500, 10000)));
+        assert_eq!(book.best_sell(), Some((100500, 20000)));
+    }
+
+    #[test]
+    fn test_cancel_replace_priority() {
+        let config = BookConfig { keep_priority_on_downsize: true, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+
+        // Downsize only: order 1 stays at the front of the queue
+        assert!(book.cancel_replace(1, 100500, 4000).unwrap().is_empty());
+        let trades = book.place_order(Side::Buy, 100500, 4000, 3).unwrap();
+        assert_eq!((trades[0].maker_id, trades[0].quantity), (1, 4000));
+
+        book.place_order(Side::Sell, 100500, 10000, 4).unwrap();
+        // Price change: order 2 moves away and back, landing behind order 4
+        book.cancel_replace(2, 101000, 10000).unwrap();
+        book.cancel_replace(2, 100500, 10000).unwrap();
+        let trades = book.place_order(Side::Buy, 100500, 10000, 5).unwrap();
+        assert_eq!(trades[0].maker_id, 4);
+
+        assert_eq!(book.cancel_replace(99, 100500, 1000), Err(OrderError::UnknownOrder(99)));
+        assert_eq!(book.cancel_replace(2, 100500, 0), Err(OrderError::InvalidQuantity(0)));
+    }
+
+    #[test]
+    fn test_cancel_replace_downsize_without_flag_loses_priority() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+
+        book.cancel_replace(1, 100500, 4000).unwrap();
+        let trades = book.place_order(Side::Buy, 100500, 4000, 3).unwrap();
+        assert_eq!(trades[0].maker_id, 2);
+    }
+
+    #[test]
+    fn test_market_order_sweeps_and_never_rests() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let empty = MarketFill { trades: Vec::new(), unfilled: 10000, slippage_penalty: 0 };
+        assert_eq!(book.place_market_order(Side::Buy, 10000, 1), Ok(empty));
+
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 150000, 10000, 3).unwrap();
+        let MarketFill { trades, unfilled, .. } = book.place_market_order(Side::Buy, 25000, 4).unwrap();
+        let prices: Vec<i64> = trades.iter().map(|t| t.price).collect();
+        assert_eq!(prices, vec![100500, 150000]);
+        assert_eq!(unfilled, 5000);
+        assert_eq!(book.best_sell(), None);
+        assert_eq!(book.best_buy(), None);
+
+        book.place_order(Side::Buy, 99500, 10000, 5).unwrap();
+        let MarketFill { trades, unfilled, .. } = book.place_market_order(Side::Sell, 4000, 6).unwrap();
+        assert_eq!((trades[0].price, unfilled), (99500, 0));
+        assert_eq!(book.place_market_order(Side::Sell, 0, 7), Err(OrderError::InvalidQuantity(0)));
+    }
+
+    #[test]
+    fn test_market_order_slippage_penalty() {
+        let config = BookConfig { market_slippage_bps_per_level: 10, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        for (id, price) in [(1, 100000), (2, 100100), (3, 100200), (4, 100000)] {
+            book.place_order(Side::Sell, price, 10000, id).unwrap();
+        }
+
+        // Touch only: no penalty
+        assert_eq!(book.place_market_order(Side::Buy, 10000, 5).unwrap().slippage_penalty, 0);
+
+        // 1 and 2 levels past the touch at 10 bps each
+        let fill = book.place_market_order(Side::Buy, 30000, 6).unwrap();
+        assert_eq!(fill.trades.len(), 3);
+        let expected = 100100 * 10000 * 10 / 10_000 + 100200 * 10000 * 20 / 10_000;
+        assert_eq!(fill.slippage_penalty, expected);
+
+        // A penalty past i64 saturates instead of wrapping
+        let config = BookConfig { market_slippage_bps_per_level: 10_000, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 1, i64::MAX / 4, 1).unwrap();
+        book.place_order(Side::Sell, 2, i64::MAX / 4, 2).unwrap();
+        book.place_order(Side::Sell, 3, i64::MAX / 4, 3).unwrap();
+        let fill = book.place_market_order(Side::Buy, i64::MAX / 4 * 3, 4).unwrap();
+        assert_eq!(fill.unfilled, 0);
+        assert_eq!(fill.slippage_penalty, i64::MAX);
+    }
+
+    #[test]
+    fn test_ioc_discards_remainder() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 2).unwrap();
+
+        let trades = book.place_order_tif(Side::Buy, 100500, 15000, 3, TimeInForce::Ioc).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(book.best_buy(), None);
+        assert_eq!(book.best_sell(), Some((101000, 10000)));
+    }
+
+    #[test]
+    fn test_fok_checks_full_quantity_across_levels() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 5000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 5000, 3).unwrap();
+        book.place_order(Side::Sell, 102000, 10000, 4).unwrap();
+
+        // 20000 crosses up to 101000; one unit more needs the 102000 level
+        assert!(book.place_order_tif(Side::Buy, 101000, 20001, 5, TimeInForce::Fok).unwrap().is_empty());
+        assert_eq!(book.order_count(), 4);
+        assert_eq!(book.best_sell(), Some((100500, 10000)));
+
+        // Partially consuming the last crossing level is enough
+        let trades = book.place_order_tif(Side::Buy, 101000, 17000, 6, TimeInForce::Fok).unwrap();
+        let filled: i64 = trades.iter().map(|t| t.quantity).sum();
+        assert_eq!(filled, 17000);
+        assert_eq!(book.best_sell(), Some((101000, 3000)));
+        assert_eq!(book.best_buy(), None);
+    }
+
+    #[test]
+    fn test_self_trade_prevention_cancels_resting() {
+        let config = BookConfig {
+            self_trade_prevention: SelfTradePrevention::CancelResting,
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order_for(7, Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order_for(8, Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order_for(7, Side::Sell, 101000, 10000, 3).unwrap();
+
+        // Owner 7's own asks are pulled; only owner 8's order trades
+        let trades = book.place_order_for(7, Side::Buy, 101000, 15000, 4).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].maker_id, trades[0].quantity), (2, 10000));
+        assert_eq!(book.best_sell(), None);
+        assert_eq!(book.best_buy(), Some((101000, 5000)));
+
+        // Without an owner match, orders trade as usual
+        book.place_order_for(8, Side::Sell, 101000, 5000, 5).unwrap();
+        assert_eq!(book.order_count(), 0);
+    }
+
+    #[test]
+    fn test_fok_excludes_self_trade_cancelled_orders() {
+        let config = BookConfig {
+            self_trade_prevention: SelfTradePrevention::CancelResting,
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order_for(7, Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order_for(8, Side::Sell, 100500, 5000, 2).unwrap();
+
+        // Only owner 8's 5000 is reachable, so the FOK is killed untouched
+        let mut order = NewOrder::limit(Side::Buy, 100500, 12000, 3);
+        order.owner_id = 7;
+        order.time_in_force = TimeInForce::Fok;
+        assert!(book.submit(order.clone()).unwrap().is_empty());
+        assert_eq!(book.best_sell(), Some((100500, 15000)));
+        assert_eq!(book.order_count(), 2);
+
+        order.quantity = 5000;
+        let trades = book.submit(order).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].maker_id, trades[0].quantity), (2, 5000));
+    }
+
+    #[test]
+    fn test_self_trade_prevention_ignores_anonymous_orders() {
+        let config = BookConfig {
+            self_trade_prevention: SelfTradePrevention::CancelResting,
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        // Two orders without an owner are not the same account
+        let trades = book.place_order(Side::Buy, 100500, 4000, 2).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].maker_id, trades[0].quantity), (1, 4000));
+
+        // An owned taker still trades with an anonymous maker
+        let trades = book.place_order_for(7, Side::Buy, 100500, 6000, 3).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(book.order_count(), 0);
+    }
+
+    #[test]
+    fn test_get_order_tracks_fills() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 2).unwrap();
+        assert!(book.contains(1));
+
+        book.place_order(Side::Buy, 100500, 4000, 3).unwrap();
+        assert_eq!(book.get_order(1).map(|o| o.quantity), Some(6000));
+        assert!(!book.contains(3));
+
+        // Fully filled and cancelled orders drop out of the index
+        book.place_order(Side::Buy, 100500, 6000, 4).unwrap();
+        assert!(!book.contains(1));
+        assert_eq!(book.get_order(1), None);
+        book.cancel_order(2).unwrap();
+        assert!(!book.contains(2));
+        assert_eq!(book.order_count(), 0);
+    }
+
+    #[test]
+    fn test_stop_orders_trigger_on_last_trade() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100000, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 102000, 10000, 4).unwrap();
+
+        // Buy stop-limit at 100500, buy stop (market) at 100000, and one
+        // above anything that will trade
+        book.place_stop_order(Side::Buy, 100500, Some(101000), 5000, 10).unwrap();
+        book.place_stop_order(Side::Buy, 100000, None, 5000, 11).unwrap();
+        book.place_stop_order(Side::Buy, 105000, None, 5000, 12).unwrap();
+        assert_eq!(book.pending_stop_count(), 3);
+
+        // One order lifts through both lower triggers; they activate lowest
+        // trigger first and trade after the aggressor
+        let trades = book.place_order(Side::Buy, 100500, 15000, 5).unwrap();
+        let takers: Vec<(u64, i64)> = trades.iter().map(|t| (t.taker_id, t.price)).collect();
+        assert_eq!(takers, vec![(5, 100000), (5, 100500), (11, 100500), (10, 101000)]);
+        assert_eq!(book.pending_stop_count(), 1);
+        assert_eq!(book.best_sell(), Some((101000, 5000)));
+    }
+
+    #[test]
+    fn test_cancel_order() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 5000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+
+        let cancelled = book.cancel_order(1).unwrap();
+        assert_eq!((cancelled.id, cancelled.quantity), (1, 10000));
+        assert_eq!(book.best_buy(), Some((99500, 5000)));
+
+        // Cancelling the last order at a level removes the level
+        book.cancel_order(3).unwrap();
+        assert_eq!(book.best_sell(), None);
+
+        assert_eq!(book.cancel_order(1), None);
+        assert_eq!(book.cancel_order(99), None);
+        assert_eq!(book.order_count(), 1);
+    }
+
+    #[test]
+    fn test_amend_quantity_priority() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+
+        // Decrease: order 1 stays at the front
+        book.amend_quantity(1, 4000).unwrap();
+        assert_eq!(book.best_sell(), Some((100500, 14000)));
+        let trades = book.place_order(Side::Buy, 100500, 1000, 3).unwrap();
+        assert_eq!(trades[0].maker_id, 1);
+
+        // Increase: order 1 goes behind order 2
+        book.amend_quantity(1, 8000).unwrap();
+        let trades = book.place_order(Side::Buy, 100500, 1000, 4).unwrap();
+        assert_eq!(trades[0].maker_id, 2);
+
+        assert_eq!(book.amend_quantity(1, 0), Err(OrderError::InvalidQuantity(0)));
+        assert_eq!(book.amend_quantity(99, 1000), Err(OrderError::UnknownOrder(99)));
+    }
+
+    #[test]
+    fn test_session_stats_reporting_currency() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100000, 20000, 1).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 2).unwrap();
+        book.place_order(Side::Buy, 101000, 30000, 3).unwrap();
+
+        let notional = 100000i128 * 20000 + 101000 * 10000;
+        assert_eq!(book.stats().volume, 30000);
+        assert_eq!(book.stats().trade_count, 2);
+        assert_eq!(book.stats().notional, notional);
+        assert_eq!(book.stats().notional_in_reporting_currency(), notional as f64);
+
+        book.set_fx_rate(0.8);
+        assert_eq!(book.stats().notional, notional);
+        assert_eq!(book.stats().notional_in_reporting_currency(), notional as f64 * 0.8);
+    }
+
+    #[test]
+    fn test_roll_session() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 100000, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 102000, 20000, 2).unwrap();
+        book.place_order(Side::Buy, 102000, 20000, 3).unwrap();
+
+        book.roll_session();
+        assert_eq!(book.stats().volume, 0);
+        assert_eq!(book.stats().trade_count, 0);
+        assert_eq!(book.stats().vwap(), None);
+        assert_eq!(book.stats().high, None);
+        // The rest of order 2 is still on the book
+        assert_eq!(book.best_sell(), Some((102000, 10000)));
+
+        let past = &book.past_sessions()[0];
+        assert_eq!(past.volume, 20000);
+        assert_eq!(past.trade_count, 2);
+        assert_eq!(past.vwap(), Some(101000.0));
+        assert_eq!((past.high, past.low), (Some(102000), Some(100000)));
+    }
+
+    #[test]
+    fn test_session_high_low() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert_eq!((book.session_high(), book.session_low()), (None, None));
+
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 100500, 5000, 2).unwrap();
+        assert_eq!((book.session_high(), book.session_low()), (Some(100500), Some(100500)));
+
+        book.place_order(Side::Sell, 101500, 10000, 3).unwrap();
+        book.place_order(Side::Buy, 101500, 10000, 4).unwrap();
+        book.place_order(Side::Buy, 99000, 10000, 5).unwrap();
+        book.place_order(Side::Sell, 99000, 10000, 6).unwrap();
+        assert_eq!((book.session_high(), book.session_low()), (Some(101500), Some(99000)));
+
+        book.roll_session();
+        assert_eq!((book.session_high(), book.session_low()), (None, None));
+    }
+
+    #[test]
+    fn test_account_notional() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order_for(7, Side::Sell, 100000, 10000, 1).unwrap();
+        book.place_order_for(8, Side::Buy, 100000, 4000, 2).unwrap();
+        book.place_order_for(9, Side::Buy, 100000, 6000, 3).unwrap();
+        book.place_order_for(8, Side::Sell, 99000, 2000, 4).unwrap();
+        book.place_order_for(7, Side::Buy, 99000, 2000, 5).unwrap();
+
+        assert_eq!(book.account_notional(7), 100000 * 10000 + 99000 * 2000);
+        assert_eq!(book.account_notional(8), 100000 * 4000 + 99000 * 2000);
+        assert_eq!(book.account_notional(9), 100000 * 6000);
+        assert_eq!(book.account_notional(10), 0);
+    }
+
+    #[test]
+    fn test_passive_aggressive_split() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order_for(7, Side::Sell, 100000, 10000, 1).unwrap();
+        book.place_order_for(8, Side::Buy, 100000, 4000, 2).unwrap();
+        book.place_order_for(8, Side::Buy, 99000, 5000, 3).unwrap();
+        book.place_order_for(7, Side::Sell, 99000, 3000, 4).unwrap();
+
+        assert_eq!(book.passive_aggressive_split(7), (4000, 3000));
+        assert_eq!(book.passive_aggressive_split(8), (3000, 4000));
+        assert_eq!(book.passive_aggressive_split(9), (0, 0));
+    }
+
+    #[test]
+    fn test_on_trade_callback_sees_trades_in_order() {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let sink = seen.clone();
+        book.set_on_trade(Box::new(move |trade| sink.lock().unwrap().push(trade.clone())));
+
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 101000, 10000, 3).unwrap();
+        let trades = book.place_order(Side::Buy, 101000, 25000, 4).unwrap();
+        assert_eq!(trades.len(), 3);
+        assert_eq!(*seen.lock().unwrap(), trades);
+
+        book.clear_on_trade();
+        book.place_order(Side::Buy, 101000, 1000, 5).unwrap();
+        assert_eq!(seen.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_hybrid_top_order_then_pro_rata() {
+        let config = BookConfig {
+            matching_policy: MatchingPolicy::Hybrid { top_order_fraction: 0.5 },
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 50, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 30, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 20, 3).unwrap();
+
+        // Order 1 takes 20 (half of 40) up front; the other 20 is shared over
+        // sizes 30/30/20 as 7/7/5 with the odd unit going to the front order
+        let trades = book.place_order(Side::Buy, 100500, 40, 4).unwrap();
+        let fills: Vec<(u64, i64)> = trades.iter().map(|t| (t.maker_id, t.quantity)).collect();
+        assert_eq!(fills, vec![(1, 28), (2, 7), (3, 5)]);
+
+        let level = &book.sell_levels[&100500];
+        let remaining: Vec<(u64, i64)> = level.iter().map(|o| (o.id, o.quantity)).collect();
+        assert_eq!(remaining, vec![(1, 22), (2, 23), (3, 15)]);
+    }
+
+    #[test]
+    fn test_pro_rata_policy() {
+        let config = BookConfig { matching_policy: MatchingPolicy::ProRata, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 30, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 70, 2).unwrap();
+        let trades = book.place_order(Side::Buy, 100500, 100, 3).unwrap();
+        let fills: Vec<(u64, i64)> = trades.iter().map(|t| (t.maker_id, t.quantity)).collect();
+        assert_eq!(fills, vec![(1, 30), (2, 70)]);
+
+        // Partial fill: 10 split 3 / 7 regardless of queue position
+        book.place_order(Side::Sell, 100500, 70, 4).unwrap();
+        book.place_order(Side::Sell, 100500, 30, 5).unwrap();
+        let trades = book.place_order(Side::Buy, 100500, 10, 6).unwrap();
+        let fills: Vec<(u64, i64)> = trades.iter().map(|t| (t.maker_id, t.quantity)).collect();
+        assert_eq!(fills, vec![(4, 7), (5, 3)]);
+    }
+
+    #[test]
+    fn test_pro_rata_allocation_rounding() {
+        assert_eq!(pro_rata_allocation(&[30, 70], 100), vec![30, 70]);
+        assert_eq!(pro_rata_allocation(&[1, 1, 1], 2), vec![1, 1, 0]);
+        assert_eq!(pro_rata_allocation(&[5, 0], 5), vec![5, 0]);
+    }
+
+    #[test]
+    fn test_max_order_quantity() {
+        let config = BookConfig { max_order_quantity: Some(1_000_000), ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        assert_eq!(
+            book.place_order(Side::Buy, 100500, 1_000_001, 2),
+            Err(OrderError::QuantityTooLarge { quantity: 1_000_001, max: 1_000_000 })
+        );
+        // Rejected before matching: the resting ask is untouched
+        assert_eq!(book.best_sell(), Some((100500, 10000)));
+
+        let trades = book.place_order(Side::Buy, 100500, 999_999, 3).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(book.best_buy(), Some((100500, 989_999)));
+    }
+
+    #[test]
+    fn test_trade_price_band() {
+        let config = BookConfig { trade_price_band_bps: Some(100.0), ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100000, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 2).unwrap();
+        // Gap: 105000 is 5% above the previous level
+        book.place_order(Side::Sell, 105000, 10000, 3).unwrap();
+
+        let trades = book.place_order(Side::Buy, 105000, 30000, 4).unwrap();
+        let prices: Vec<i64> = trades.iter().map(|t| t.price).collect();
+        assert_eq!(prices, vec![100000, 100500]);
+
+        // The out-of-band level is untouched and the remainder didn't rest
+        assert_eq!(book.best_sell(), Some((105000, 10000)));
+        assert_eq!(book.best_buy(), None);
+    }
+
+    #[test]
+    fn test_odd_lot_remainder_rests() {
+        let config = BookConfig {
+            odd_lot_policy: OddLotPolicy::RoundLotsOnly { lot_size: 1000 },
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        let trades = book.place_order(Side::Buy, 100500, 2500, 2).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].quantity, 2000);
+        // The 500 odd lot rests instead of executing
+        assert_eq!(book.best_buy(), Some((100500, 500)));
+        assert_eq!(book.best_sell(), Some((100500, 8000)));
+    }
+
+    #[test]
+    fn test_fok_rounds_to_whole_lots() {
+        let config = BookConfig {
+            odd_lot_policy: OddLotPolicy::RoundLotsOnly { lot_size: 10 },
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 15, 1).unwrap();
+
+        // Only one whole lot of the 15 can trade, so the FOK is killed
+        let trades = book.place_order_tif(Side::Buy, 100500, 15, 2, TimeInForce::Fok).unwrap();
+        assert!(trades.is_empty());
+        assert_eq!(book.best_sell(), Some((100500, 15)));
+
+        // The sub-lot order ahead in the queue is passed over
+        book.place_order(Side::Sell, 100500, 5, 3).unwrap();
+        book.place_order(Side::Sell, 100500, 10, 4).unwrap();
+        let trades = book.place_order_tif(Side::Buy, 100500, 20, 5, TimeInForce::Fok).unwrap();
+        assert_eq!(trades.iter().map(|t| (t.maker_id, t.quantity)).collect::<Vec<_>>(), vec![(1, 10), (4, 10)]);
+        assert_eq!(book.best_sell(), Some((100500, 10)));
+    }
+
+    #[test]
+    fn test_odd_lot_policy_only_applies_to_price_time() {
+        let config = BookConfig {
+            matching_policy: MatchingPolicy::ProRata,
+            odd_lot_policy: OddLotPolicy::RoundLotsOnly { lot_size: 1000 },
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 100500, 10000, 1).unwrap();
+
+        let trades = book.place_order(Side::Buy, 100500, 2500, 2).unwrap();
+        assert_eq!(trades.iter().map(|t| t.quantity).sum::<i64>(), 2500);
+        assert_eq!(book.best_buy(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "lot size must be positive")]
+    fn test_zero_lot_size_rejected() {
+        let config = BookConfig {
+            odd_lot_policy: OddLotPolicy::RoundLotsOnly { lot_size: 0 },
+            ..BookConfig::default()
+        };
+        OrderBook::with_config("Valhalla/USD".to_string(), config);
+    }
+
+    #[test]
+    fn test_overflow_guards() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        assert_eq!(
+            book.place_order(Side::Buy, 100000, i64::MAX / 2, 1),
+            Err(OrderError::NotionalOverflow { price: 100000, quantity: i64::MAX / 2 })
+        );
+        assert_eq!(book.order_count(), 0);
+
+        // Each order fits on its own; their level total saturates
+        book.place_order(Side::Buy, 1, i64::MAX - 1, 2).unwrap();
+        book.place_order(Side::Buy, 1, i64::MAX - 1, 3).unwrap();
+        assert_eq!(book.best_buy(), Some((1, i64::MAX)));
+        assert_eq!(book.depth_snapshot(1).bids, vec![(1, i64::MAX)]);
+
+        // Matching, fill checks and analytics over that level don't overflow
+        assert_eq!(book.estimate_fill(Side::Sell, 5).filled_quantity, 5);
+        assert_eq!(book.touch_concentration(Side::Buy), Some(1.0));
+        let trades = book.place_order_tif(Side::Sell, 1, 5, 4, TimeInForce::Fok).unwrap();
+        assert_eq!(trades.iter().map(|t| t.quantity).sum::<i64>(), 5);
+
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Sell, 1, i64::MAX - 1, 1).unwrap();
+        book.place_order(Side::Sell, 1, i64::MAX - 1, 2).unwrap();
+        assert_eq!(book.estimate_fill(Side::Buy, 5).filled_quantity, 5);
+        let trades = book.place_order_tif(Side::Buy, 1, 5, 3, TimeInForce::Fok).unwrap();
+        assert_eq!(trades.iter().map(|t| t.quantity).sum::<i64>(), 5);
+
+        let config = BookConfig { matching_policy: MatchingPolicy::ProRata, ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        book.place_order(Side::Sell, 1, i64::MAX - 1, 1).unwrap();
+        book.place_order(Side::Sell, 1, i64::MAX - 1, 2).unwrap();
+        let trades = book.place_order(Side::Buy, 1, 5, 3).unwrap();
+        assert_eq!(trades.iter().map(|t| t.quantity).sum::<i64>(), 5);
+
+        // Running volume totals saturate across near-maximal trades
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        for id in [1, 3] {
+            book.place_order_for(7, Side::Sell, 1, i64::MAX - 1, id).unwrap();
+            book.place_order_for(8, Side::Buy, 1, i64::MAX - 1, id + 1).unwrap();
+        }
+        assert_eq!(book.stats().volume, i64::MAX);
+        assert_eq!(book.stats().trade_count, 2);
+        assert_eq!(book.passive_aggressive_split(7), (i64::MAX, 0));
+        assert_eq!(book.passive_aggressive_split(8), (0, i64::MAX));
+    }
+
+    #[test]
+    fn test_tick_table_bands() {
+        // 1 cent ticks below $100, 5 cent ticks from $100 up
+        let config = BookConfig {
+            tick_table: TickTable::new(1).with_band(10000, 5),
+            ..BookConfig::default()
+        };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+
+        assert!(book.place_order(Side::Buy, 9999, 10000, 1).is_ok());
+        assert_eq!(
+            book.place_order(Side::Buy, 10001, 10000, 2),
+            Err(OrderError::OffTick { price: 10001, tick: 5 })
+        );
+        assert!(book.place_order(Side::Sell, 10005, 10000, 3).is_ok());
+        assert_eq!(book.best_sell(), Some((10005, 10000)));
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of wall-clock time, in unix millis, for order and trade
+/// timestamps.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> u64;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// Test clock. Returns scripted times in order, then keeps returning the
+/// last one until moved with `set` or `advance`. Clones share the same
+/// time, so a test can keep a handle to a clock it gave to a book.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    scripted: VecDeque<u64>,
+    current: u64,
+}
+
+impl MockClock {
+    /// A clock stopped at `start`.
+    pub fn new(start: u64) -> Self {
+        Self::scripted(vec![start])
+    }
+
+    pub fn scripted(times: Vec<u64>) -> Self {
+        let state = MockState { scripted: times.into(), current: 0 };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Moves the clock to `now`, dropping any remaining scripted times.
+    pub fn set(&self, now: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.scripted.clear();
+        state.current = now;
+    }
+
+    pub fn advance(&self, millis: u64) {
+        let now = self.now() + millis;
+        self.set(now);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        if let Some(next) = state.scripted.pop_front() {
+            state.current = next;
+        }
+        state.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::scripted(vec![1_000, 2_500]);
+        let handle = clock.clone();
+        assert_eq!(clock.now(), 1_000);
+        assert_eq!(clock.now(), 2_500);
+        assert_eq!(clock.now(), 2_500);
+
+        handle.advance(500);
+        assert_eq!(clock.now(), 3_000);
+        handle.set(10_000);
+        assert_eq!(clock.now(), 10_000);
+    }
+}
@@ -0,0 +1,715 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::book::{OrderBook, Side, Trade};
+use crate::clock::{Clock, SystemClock};
+use crate::error::OrderError;
+use crate::script::OrderEvent;
+
+/// Minimum run of same-aggressor trades that counts as a burst.
+pub const IGNITION_MIN_BURST: usize = 3;
+
+/// Separate taker orders a maker must absorb at one price to look like an
+/// iceberg.
+pub const ICEBERG_MIN_HITS: usize = 3;
+
+/// Volume summary of the tape over one time bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedPrint {
+    /// Bucket start, unix millis.
+    pub bucket_start: u64,
+    /// Volume-weighted average price, in cents.
+    pub vwap: f64,
+    pub volume: i64,
+    pub trade_count: usize,
+}
+
+/// OHLCV bar over one time bucket. Prices in cents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candle {
+    /// Bucket start, unix millis.
+    pub bucket_start: u64,
+    pub open: i64,
+    pub high: i64,
+    pub low: i64,
+    pub close: i64,
+    pub volume: i64,
+}
+
+/// Exchange fees in basis points of trade notional. Negative values are
+/// rebates paid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FeeSchedule {
+    pub maker_bps: i64,
+    pub taker_bps: i64,
+}
+
+pub struct TradingEngine {
+    book: OrderBook,
+    next_order_id: u64,
+    trades_history: Vec<(Trade, u64, u64)>, // Trade + unix millis + settles at (unix millis)
+    settlement_delay: u64,
+    fee_schedule: FeeSchedule,
+    fees_collected: i64,
+    fees_by_order: HashMap<u64, i64>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for TradingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradingEngine {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// An engine whose book and tape take their timestamps from `clock`.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            book: OrderBook::with_clock("Valhalla/USD".to_string(), clock.clone()),
+            next_order_id: 1000,
+            trades_history: Vec::new(),
+            settlement_delay: 0,
+            fee_schedule: FeeSchedule::default(),
+            fees_collected: 0,
+            fees_by_order: HashMap::new(),
+            clock,
+        }
+    }
+
+    /// Milliseconds from execution to settlement for trades recorded from
+    /// now on.
+    pub fn set_settlement_delay(&mut self, delay_ms: u64) {
+        self.settlement_delay = delay_ms;
+    }
+
+    /// Notional (cents * 0.0001 units) of recorded trades that settle after
+    /// `now` (unix millis).
+    pub fn unsettled_notional(&self, now: u64) -> i128 {
+        self.trades_history
+            .iter()
+            .filter(|(_, _, settles_at)| *settles_at > now)
+            .map(|(trade, _, _)| trade.price as i128 * trade.quantity as i128)
+            .sum()
+    }
+
+    /// Applies to trades recorded from now on.
+    pub fn set_fee_schedule(&mut self, fee_schedule: FeeSchedule) {
+        self.fee_schedule = fee_schedule;
+    }
+
+    /// Net fees charged across all recorded trades, rebates subtracted, in
+    /// notional units (cents * 0.0001 units).
+    pub fn fees_collected(&self) -> i64 {
+        self.fees_collected
+    }
+
+    /// Net fees charged to one order id, as maker and as taker.
+    pub fn fees_for_order(&self, id: u64) -> i64 {
+        self.fees_by_order.get(&id).copied().unwrap_or(0)
+    }
+
+    pub fn place_order(&mut self, side: Side, price: f64, quantity: f64) -> Result<Vec<Trade>, String> {
+        // Convert to integer representation (price in cents, quantity in 0.0001 units)
+        let price_int = to_grid(price, 100.0)
+            .ok_or_else(|| format!("Price {} is not a whole number of cents", price))?;
+        let quantity_int = to_grid(quantity, 10000.0)
+            .ok_or_else(|| format!("Quantity {} has more than 4 decimal places", quantity))?;
+
+        if quantity_int <= 0 || price_int <= 0 {
+            return Err("Price and quantity must be positive".to_string());
+        }
+
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+
+        println!("\n⚡ INCOMING ORDER:");
+        println!("   Order #{}: {} {:.4} Valhalla @ ${:.2}",
+            order_id, side, quantity, price);
+
+        let trades = self.book
+            .place_order(side, price_int, quantity_int, order_id)
+            .map_err(|e| e.to_string())?;
+
+        if !trades.is_empty() {
+            println!("\n🎯 TRADES EXECUTED:");
+            for (i, trade) in trades.iter().enumerate() {
+                let trade_price = trade.price as f64 / 100.0;
+                let trade_qty = trade.quantity as f64 / 10000.0;
+                let trade_value = trade_price * trade_qty;
+
+                println!("   Trade #{}: {:.4} Valhalla @ ${:.2} = ${:.2} (Maker: #{}, Taker: #{})",
+                    i + 1, trade_qty, trade_price, trade_value, trade.maker_id, trade.taker_id);
+
+                self.record_trade(trade.clone(), self.clock.now());
+            }
+        } else {
+            println!("   ➕ Order added to book (no matches)");
+        }
+
+        Ok(trades)
+    }
+
+    fn record_trade(&mut self, trade: Trade, timestamp: u64) {
+        // Fees round toward zero
+        let notional = trade.price as i128 * trade.quantity as i128;
+        let maker_fee = (notional * self.fee_schedule.maker_bps as i128 / 10_000) as i64;
+        let taker_fee = (notional * self.fee_schedule.taker_bps as i128 / 10_000) as i64;
+        *self.fees_by_order.entry(trade.maker_id).or_default() += maker_fee;
+        *self.fees_by_order.entry(trade.taker_id).or_default() += taker_fee;
+        self.fees_collected += maker_fee + taker_fee;
+
+        let settles_at = timestamp + self.settlement_delay;
+        self.trades_history.push((trade, timestamp, settles_at));
+    }
+
+    /// Buckets the tape into `bucket_secs` windows, oldest first. Empty
+    /// buckets are omitted.
+    pub fn aggregated_tape(&self, bucket_secs: u64) -> Vec<AggregatedPrint> {
+        let bucket_ms = bucket_secs.max(1) * 1000;
+        let mut buckets: BTreeMap<u64, (i128, i64, usize)> = BTreeMap::new();
+        for (trade, timestamp, _) in &self.trades_history {
+            let entry = buckets.entry(timestamp / bucket_ms).or_default();
+            entry.0 += trade.price as i128 * trade.quantity as i128;
+            entry.1 = entry.1.saturating_add(trade.quantity);
+            entry.2 += 1;
+        }
+
+        buckets
+            .into_iter()
+            .map(|(bucket, (notional, volume, trade_count))| AggregatedPrint {
+                bucket_start: bucket * bucket_ms,
+                vwap: notional as f64 / volume as f64,
+                volume,
+                trade_count,
+            })
+            .collect()
+    }
+
+    /// OHLCV candles over `interval_secs` buckets of the tape, oldest first.
+    /// Intervals without trades are skipped, not carried forward, so
+    /// consecutive candles need not be adjacent in time.
+    pub fn build_candles(&self, interval_secs: u64) -> Vec<Candle> {
+        let interval_ms = interval_secs.max(1) * 1000;
+        let mut candles: Vec<Candle> = Vec::new();
+        for (trade, timestamp, _) in &self.trades_history {
+            let bucket_start = timestamp / interval_ms * interval_ms;
+            match candles.last_mut() {
+                Some(candle) if candle.bucket_start == bucket_start => {
+                    candle.high = candle.high.max(trade.price);
+                    candle.low = candle.low.min(trade.price);
+                    candle.close = trade.price;
+                    candle.volume = candle.volume.saturating_add(trade.quantity);
+                }
+                _ => candles.push(Candle {
+                    bucket_start,
+                    open: trade.price,
+                    high: trade.price,
+                    low: trade.price,
+                    close: trade.price,
+                    volume: trade.quantity,
+                }),
+            }
+        }
+        candles
+    }
+
+    /// Flags possible momentum ignition: a burst of at least
+    /// `IGNITION_MIN_BURST` consecutive trades with the same aggressor that
+    /// push the price in the aggressor's direction (never against it, and
+    /// strictly overall), followed within the next `window` trades by a print
+    /// back at or through the price the burst started from. Returns the
+    /// `trades_history` index of the first trade of each flagged burst.
+    pub fn detect_momentum_ignition(&self, window: u64) -> Vec<usize> {
+        let tape = &self.trades_history;
+        let mut flagged = Vec::new();
+        let mut start = 0;
+
+        while start < tape.len() {
+            let aggressor = tape[start].0.aggressor;
+            let with_trend = |from: i64, to: i64| match aggressor {
+                Side::Buy => to >= from,
+                Side::Sell => to <= from,
+            };
+
+            let mut end = start;
+            while end + 1 < tape.len()
+                && tape[end + 1].0.aggressor == aggressor
+                && with_trend(tape[end].0.price, tape[end + 1].0.price)
+            {
+                end += 1;
+            }
+
+            let start_price = tape[start].0.price;
+            let moved = tape[end].0.price != start_price;
+            if end - start + 1 >= IGNITION_MIN_BURST && moved {
+                let reversed = tape[end + 1..]
+                    .iter()
+                    .take(window as usize)
+                    .any(|(trade, _, _)| with_trend(trade.price, start_price));
+                if reversed {
+                    flagged.push(start);
+                }
+            }
+
+            start = end + 1;
+        }
+
+        flagged
+    }
+
+    /// Makers that look like icebergs, as `(maker_id, total filled)` sorted
+    /// by id. The tape doesn't show displayed size, so the heuristic is
+    /// behavioural: an ordinary resting order is usually used up by one or
+    /// two takers, while a maker that keeps absorbing taker after taker at
+    /// the same price (at least `ICEBERG_MIN_HITS` distinct taker orders)
+    /// is likely refilling a small visible clip from a hidden reserve. The
+    /// total is everything the maker filled at that price. A large plain
+    /// order picked off in small pieces looks the same, so expect false
+    /// positives.
+    pub fn infer_icebergs(&self) -> Vec<(u64, i64)> {
+        let mut hits: BTreeMap<(u64, i64), (HashSet<u64>, i64)> = BTreeMap::new();
+        for (trade, _, _) in &self.trades_history {
+            let (takers, filled) = hits.entry((trade.maker_id, trade.price)).or_default();
+            takers.insert(trade.taker_id);
+            *filled = filled.saturating_add(trade.quantity);
+        }
+        hits.into_iter()
+            .filter(|(_, (takers, _))| takers.len() >= ICEBERG_MIN_HITS)
+            .map(|((maker_id, _), (_, filled))| (maker_id, filled))
+            .collect()
+    }
+
+    /// `(buy volume, sell volume)` that traded better than the tape's VWAP:
+    /// buy-aggressor fills strictly below it and sell-aggressor fills
+    /// strictly above it. Fills exactly at the VWAP count for neither.
+    pub fn volume_better_than_vwap(&self) -> (i64, i64) {
+        let (notional, volume) = self.trades_history.iter().fold((0i128, 0i128), |(n, v), (trade, _, _)| {
+            (n + trade.price as i128 * trade.quantity as i128, v + trade.quantity as i128)
+        });
+        if volume == 0 {
+            return (0, 0);
+        }
+        let vwap = notional as f64 / volume as f64;
+
+        let mut better = (0i64, 0i64);
+        for (trade, _, _) in &self.trades_history {
+            match trade.aggressor {
+                Side::Buy if (trade.price as f64) < vwap => better.0 = better.0.saturating_add(trade.quantity),
+                Side::Sell if (trade.price as f64) > vwap => better.1 = better.1.saturating_add(trade.quantity),
+                _ => {}
+            }
+        }
+        better
+    }
+
+    /// Number of distinct prices that have traded this session.
+    pub fn distinct_levels_traded(&self) -> usize {
+        self.trades_history
+            .iter()
+            .map(|(trade, _, _)| trade.price)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// `(unix millis, running signed volume)` after each trade on the tape:
+    /// buy-aggressor volume adds, sell-aggressor volume subtracts.
+    pub fn cumulative_volume_delta(&self) -> Vec<(u64, i64)> {
+        let mut delta = 0i64;
+        self.trades_history
+            .iter()
+            .map(|(trade, timestamp, _)| {
+                delta = match trade.aggressor {
+                    Side::Buy => delta.saturating_add(trade.quantity),
+                    Side::Sell => delta.saturating_sub(trade.quantity),
+                };
+                (*timestamp, delta)
+            })
+            .collect()
+    }
+
+    /// Expected price move in ticks from a naive linear model:
+    /// `sensitivity * (B - S) / (B + S)`, where `B` and `S` are the buy- and
+    /// sell-aggressor volume over the last `window` trades on the tape. It
+    /// ignores depth, trade timing and mean reversion, so treat it as a
+    /// rough signal. 0.0 with no trades.
+    pub fn predicted_drift(&self, window: usize, sensitivity: f64) -> f64 {
+        let (buy, sell) = self.trades_history.iter().rev().take(window).fold((0i64, 0i64), |(buy, sell), (trade, _, _)| {
+            match trade.aggressor {
+                Side::Buy => (buy.saturating_add(trade.quantity), sell),
+                Side::Sell => (buy, sell.saturating_add(trade.quantity)),
+            }
+        });
+        if buy == 0 && sell == 0 {
+            return 0.0;
+        }
+        sensitivity * (buy - sell) as f64 / (buy as f64 + sell as f64)
+    }
+
+    pub fn apply_event(&mut self, event: &OrderEvent) -> Result<Vec<Trade>, String> {
+        match *event {
+            OrderEvent::Place { side, price, quantity } => self.place_order(side, price, quantity),
+            OrderEvent::Cancel { id } => self.book
+                .cancel_order(id)
+                .map(|_| Vec::new())
+                .ok_or_else(|| OrderError::UnknownOrder(id).to_string()),
+        }
+    }
+
+    pub fn display_main_view(&self) {
+        // Clear screen
+        print!("\x1B[2J\x1B[1;1H");
+
+        println!("🚀 VALHALLA TRADING ENGINE");
+        println!("═══════════════════════════════════════════════════════════════════════");
+
+        // Display order book and time & sales side by side
+        self.display_book_and_sales();
+    }
+
+    fn display_book_and_sales(&self) {
+        // Get order book lines
+        let mut book_lines = Vec::new();
+
+        book_lines.push("📊 ORDER BOOK".to_string());
+        book_lines.push("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string());
+
+        book_lines.extend(self.book.ladder_lines(5));
+
+        // Get time & sales lines
+        let mut sales_lines = vec![
+            "📈 TIME & SALES".to_string(),
+            "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string(),
+            "Time     │ Price    │ Size     │ Side".to_string(),
+            "─────────┼──────────┼──────────┼─────".to_string(),
+        ];
+
+        // Show last 10 trades
+        for (trade, timestamp, _) in self.trades_history.iter().rev().take(10) {
+            let price = trade.price as f64 / 100.0;
+            let qty = trade.quantity as f64 / 10000.0;
+            sales_lines.push(format!("{} │ ${:>7.2} │ {:>8.4} │ {}",
+                format_time_of_day(*timestamp), price, qty, trade.aggressor));
+        }
+
+        if self.trades_history.is_empty() {
+            sales_lines.push("No trades yet...".to_string());
+        }
+
+        // Print side by side
+        let max_lines = std::cmp::max(book_lines.len(), sales_lines.len());
+        let empty_string = String::new();
+
+        for i in 0..max_lines {
+            let left = book_lines.get(i).map(|s| format!("{:<35}", s)).unwrap_or_else(|| " ".repeat(35));
+            let right = sales_lines.get(i).unwrap_or(&empty_string);
+            println!("{}│ {}", left, right);
+        }
+
+        println!("═══════════════════════════════════════════════════════════════════════");
+    }
+
+    pub fn seed_market_data(&mut self) {
+        println!("🌱 Seeding Valhalla market with initial orders...\n");
+
+        // Valhalla/USD set around $1000
+        let valcoin_orders = vec![
+            (Side::Buy, 995.0, 15.0),
+            (Side::Buy, 990.0, 25.0),
+            (Side::Buy, 985.0, 35.0),
+            (Side::Buy, 980.0, 45.0),
+            (Side::Sell, 1005.0, 20.0),
+            (Side::Sell, 1010.0, 30.0),
+            (Side::Sell, 1015.0, 40.0),
+            (Side::Sell, 1020.0, 50.0),
+        ];
+
+        for (side, price, qty) in valcoin_orders {
+            let _ = self.place_order(side, price, qty);
+        }
+    }
+}
+
+// `value * scale` as an integer, or None when `value` has precision below
+// the grid. Rounds rather than truncates, since e.g. 10.07 * 100.0 is
+// 1006.9999999999999 in binary floating point.
+fn to_grid(value: f64, scale: f64) -> Option<i64> {
+    let scaled = value * scale;
+    let rounded = scaled.round();
+    ((scaled - rounded).abs() < 1e-6 && rounded.abs() < i64::MAX as f64).then_some(rounded as i64)
+}
+
+// HH:MM:SS (UTC) for a unix millis timestamp
+fn format_time_of_day(timestamp: u64) -> String {
+    let secs = timestamp / 1000;
+    format!("{:02}:{:02}:{:02}", (secs / 3600) % 24, (secs / 60) % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A maker 1 / taker 2 print with the maker on the other side from
+    /// `aggressor`.
+    fn trade(aggressor: Side, price: i64, quantity: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            maker_id: 1,
+            taker_id: 2,
+            aggressor,
+            maker_side: aggressor.opposite(),
+            maker_owner: 0,
+            taker_owner: 0,
+        }
+    }
+
+    #[test]
+    fn test_valcoin_trading_engine() {
+        let mut engine = TradingEngine::new();
+
+        // Test placing orders
+        let trades = engine.place_order(Side::Buy, 1000.0, 10.0).unwrap();
+        assert!(trades.is_empty()); // No matching orders
+
+        let trades = engine.place_order(Side::Sell, 1000.0, 5.0).unwrap();
+        assert_eq!(trades.len(), 1); // Should match
+        assert_eq!(trades[0].quantity, 50000); // 5.0 * 10000
+    }
+
+    #[test]
+    fn test_price_conversion_rounds_to_grid() {
+        let mut engine = TradingEngine::new();
+        engine.place_order(Side::Buy, 10.07, 1.0).unwrap();
+        assert_eq!(engine.book.best_buy(), Some((1007, 10000)));
+
+        engine.place_order(Side::Sell, 20.0, 0.1 + 0.2).unwrap();
+        assert_eq!(engine.book.best_sell(), Some((2000, 3000)));
+
+        assert!(engine.place_order(Side::Buy, 10.005, 1.0).unwrap_err().contains("whole number of cents"));
+        assert!(engine.place_order(Side::Buy, 10.0, 0.00001).is_err());
+        assert_eq!(engine.book.order_count(), 2);
+    }
+
+    #[test]
+    fn test_time_and_sales() {
+        let mut engine = TradingEngine::new();
+
+        // Place orders that will trade
+        engine.place_order(Side::Buy, 1000.0, 10.0).unwrap();
+        let trades = engine.place_order(Side::Sell, 1000.0, 5.0).unwrap();
+
+        assert_eq!(trades.len(), 1);
+        assert_eq!(engine.trades_history.len(), 1);
+    }
+
+    #[test]
+    fn test_aggregated_tape() {
+        let mut engine = TradingEngine::new();
+
+        // Two prints in the first minute, one in the next
+        engine.record_trade(trade(Side::Buy, 100000, 10000), 60_000);
+        engine.record_trade(trade(Side::Buy, 100200, 30000), 119_999);
+        engine.record_trade(trade(Side::Buy, 100500, 20000), 120_000);
+
+        let tape = engine.aggregated_tape(60);
+        assert_eq!(tape.len(), 2);
+
+        assert_eq!(tape[0].bucket_start, 60_000);
+        assert_eq!(tape[0].volume, 40000);
+        assert_eq!(tape[0].trade_count, 2);
+        assert_eq!(tape[0].vwap, 100150.0);
+
+        assert_eq!(tape[1].bucket_start, 120_000);
+        assert_eq!(tape[1].volume, 20000);
+        assert_eq!(tape[1].vwap, 100500.0);
+    }
+
+    #[test]
+    fn test_fee_accounting() {
+        let mut engine = TradingEngine::new();
+        engine.set_fee_schedule(FeeSchedule { maker_bps: -1, taker_bps: 2 });
+        engine.place_order(Side::Sell, 1000.0, 5.0).unwrap();
+        engine.place_order(Side::Buy, 1000.0, 2.0).unwrap();
+
+        // Notional 100000 cents * 20000 units = 2_000_000_000
+        assert_eq!(engine.fees_for_order(1000), -200_000);
+        assert_eq!(engine.fees_for_order(1001), 400_000);
+        assert_eq!(engine.fees_collected(), 200_000);
+    }
+
+    #[test]
+    fn test_unsettled_notional() {
+        let mut engine = TradingEngine::new();
+        engine.set_settlement_delay(2_000);
+        engine.record_trade(trade(Side::Buy, 100000, 10000), 10_000);
+        engine.record_trade(trade(Side::Buy, 101000, 20000), 11_000);
+
+        let first = 100000i128 * 10000;
+        let second = 101000i128 * 20000;
+        assert_eq!(engine.unsettled_notional(10_000), first + second);
+        assert_eq!(engine.unsettled_notional(12_000), second);
+        assert_eq!(engine.unsettled_notional(13_000), 0);
+    }
+
+    #[test]
+    fn test_tape_timestamps_from_clock() {
+        let clock = crate::clock::MockClock::new(60_000);
+        let mut engine = TradingEngine::with_clock(Arc::new(clock.clone()));
+        engine.place_order(Side::Sell, 1000.0, 5.0).unwrap();
+        engine.place_order(Side::Buy, 1000.0, 1.0).unwrap();
+        clock.set(125_000);
+        engine.place_order(Side::Buy, 1000.0, 1.0).unwrap();
+
+        let stamps: Vec<u64> = engine.trades_history.iter().map(|(_, ts, _)| *ts).collect();
+        assert_eq!(stamps, vec![60_000, 125_000]);
+        assert_eq!(engine.build_candles(60).len(), 2);
+    }
+
+    #[test]
+    fn test_cumulative_volume_delta() {
+        let mut engine = TradingEngine::new();
+        assert!(engine.cumulative_volume_delta().is_empty());
+
+        engine.record_trade(trade(Side::Buy, 100000, 10000), 1_000);
+        engine.record_trade(trade(Side::Buy, 100000, 5000), 2_000);
+        engine.record_trade(trade(Side::Sell, 100000, 30000), 3_000);
+        engine.record_trade(trade(Side::Buy, 100000, 2000), 4_000);
+
+        assert_eq!(engine.cumulative_volume_delta(), vec![
+            (1_000, 10000),
+            (2_000, 15000),
+            (3_000, -15000),
+            (4_000, -13000),
+        ]);
+    }
+
+    #[test]
+    fn test_predicted_drift() {
+        let mut engine = TradingEngine::new();
+        assert_eq!(engine.predicted_drift(10, 4.0), 0.0);
+
+        engine.record_trade(trade(Side::Sell, 100000, 50000), 1_000);
+        engine.record_trade(trade(Side::Buy, 100000, 30000), 2_000);
+        engine.record_trade(trade(Side::Buy, 100000, 10000), 3_000);
+        // Last two trades are all buying; the window drops the sell
+        assert_eq!(engine.predicted_drift(2, 4.0), 4.0);
+
+        engine.record_trade(trade(Side::Sell, 100000, 40000), 4_000);
+        assert!(engine.predicted_drift(3, 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_infer_icebergs() {
+        let mut engine = TradingEngine::new();
+
+        // Maker 1 shows 1.0 and keeps coming back for new takers
+        for taker in 10..14 {
+            engine.record_trade(Trade { maker_id: 1, taker_id: taker, ..trade(Side::Buy, 100000, 10000) }, 1_000 * taker);
+        }
+        // Maker 2 is taken out by two takers
+        engine.record_trade(Trade { maker_id: 2, taker_id: 20, ..trade(Side::Buy, 100000, 30000) }, 20_000);
+        engine.record_trade(Trade { maker_id: 2, taker_id: 21, ..trade(Side::Buy, 100000, 20000) }, 21_000);
+        // Maker 3 fills one taker in several prints
+        for _ in 0..3 {
+            engine.record_trade(Trade { maker_id: 3, taker_id: 30, ..trade(Side::Buy, 100000, 5000) }, 30_000);
+        }
+
+        assert_eq!(engine.infer_icebergs(), vec![(1, 40000)]);
+    }
+
+    #[test]
+    fn test_volume_better_than_vwap() {
+        let mut engine = TradingEngine::new();
+        assert_eq!(engine.volume_better_than_vwap(), (0, 0));
+
+        // VWAP is exactly $1000.00
+        engine.record_trade(trade(Side::Buy, 99000, 10000), 1_000);
+        engine.record_trade(trade(Side::Buy, 101000, 10000), 2_000);
+        engine.record_trade(trade(Side::Sell, 99500, 20000), 3_000);
+        engine.record_trade(trade(Side::Sell, 100500, 20000), 4_000);
+        engine.record_trade(trade(Side::Buy, 100000, 5000), 5_000);
+
+        assert_eq!(engine.volume_better_than_vwap(), (10000, 20000));
+    }
+
+    #[test]
+    fn test_build_candles() {
+        let mut engine = TradingEngine::new();
+
+        engine.record_trade(trade(Side::Buy, 100000, 10000), 60_000);
+        engine.record_trade(trade(Side::Buy, 100500, 20000), 75_000);
+        engine.record_trade(trade(Side::Buy, 99500, 5000), 90_000);
+        engine.record_trade(trade(Side::Buy, 100200, 30000), 119_999);
+        // Nothing in the 120s bucket
+        engine.record_trade(trade(Side::Buy, 101000, 10000), 185_000);
+
+        let candles = engine.build_candles(60);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0], Candle {
+            bucket_start: 60_000,
+            open: 100000,
+            high: 100500,
+            low: 99500,
+            close: 100200,
+            volume: 65000,
+        });
+        assert_eq!(candles[1].bucket_start, 180_000);
+        assert_eq!((candles[1].open, candles[1].close), (101000, 101000));
+    }
+
+    #[test]
+    fn test_tape_volume_saturates() {
+        let mut engine = TradingEngine::new();
+        engine.record_trade(trade(Side::Buy, 1, i64::MAX - 1), 60_000);
+        engine.record_trade(trade(Side::Buy, 1, i64::MAX - 1), 61_000);
+
+        assert_eq!(engine.build_candles(60)[0].volume, i64::MAX);
+        assert_eq!(engine.aggregated_tape(60)[0].volume, i64::MAX);
+        assert_eq!(engine.cumulative_volume_delta().last(), Some(&(61_000, i64::MAX)));
+        assert_eq!(engine.predicted_drift(2, 4.0), 4.0);
+        assert_eq!(engine.infer_icebergs(), Vec::new());
+        let histogram = crate::analytics::fill_price_histogram(&vec![trade(Side::Buy, 1, i64::MAX - 1); 2]);
+        assert_eq!(histogram[&1], i64::MAX);
+    }
+
+    #[test]
+    fn test_detect_momentum_ignition() {
+        let mut engine = TradingEngine::new();
+        let mut print = |price, aggressor| {
+            engine.record_trade(trade(aggressor, price, 10000), 0);
+        };
+
+        print(100000, Side::Sell);
+        // Aggressive buying walks the price up...
+        print(100000, Side::Buy);
+        print(100500, Side::Buy);
+        print(101000, Side::Buy);
+        // ...then it collapses back through the starting level
+        print(100500, Side::Sell);
+        print(99900, Side::Sell);
+
+        assert_eq!(engine.detect_momentum_ignition(2), vec![1]);
+        // The reversal comes two trades after the burst
+        assert!(engine.detect_momentum_ignition(1).is_empty());
+    }
+
+    #[test]
+    fn test_distinct_levels_traded() {
+        let mut engine = TradingEngine::new();
+        engine.seed_market_data();
+        assert_eq!(engine.distinct_levels_traded(), 0);
+
+        // Sweep $1005 and $1010, then lift $1010 again
+        engine.place_order(Side::Buy, 1010.0, 25.0).unwrap();
+        engine.place_order(Side::Buy, 1010.0, 5.0).unwrap();
+        assert_eq!(engine.distinct_levels_traded(), 2);
+
+        engine.place_order(Side::Sell, 990.0, 20.0).unwrap();
+        assert_eq!(engine.trades_history.len(), 5);
+        assert_eq!(engine.distinct_levels_traded(), 4);
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    OffTick { price: i64, tick: i64 },
+    Halted,
+    UnknownOrder(u64),
+    InvalidQuantity(i64),
+    InvalidPrice(i64),
+    QuantityTooLarge { quantity: i64, max: i64 },
+    NotionalOverflow { price: i64, quantity: i64 },
+    UnknownSymbol(String),
+    DuplicateOrderId(u64),
+    WouldCross { price: i64, touch: i64 },
+    WouldLock { price: i64, quote: i64 },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::OffTick { price, tick } => {
+                write!(f, "Price {} is not a multiple of the {} tick for its band", price, tick)
+            }
+            OrderError::Halted => write!(f, "Trading is halted"),
+            OrderError::UnknownOrder(id) => write!(f, "Order #{} is not resting on the book", id),
+            OrderError::InvalidQuantity(quantity) => write!(f, "Quantity {} must be positive", quantity),
+            OrderError::InvalidPrice(price) => write!(f, "Price {} must be positive", price),
+            OrderError::QuantityTooLarge { quantity, max } => {
+                write!(f, "Quantity {} exceeds the maximum order size of {}", quantity, max)
+            }
+            OrderError::NotionalOverflow { price, quantity } => {
+                write!(f, "Order value {} x {} is too large to represent", price, quantity)
+            }
+            OrderError::UnknownSymbol(symbol) => write!(f, "Symbol {} is not listed", symbol),
+            OrderError::DuplicateOrderId(id) => write!(f, "Order id #{} is already in use", id),
+            OrderError::WouldCross { price, touch } => {
+                write!(f, "Post-only order at {} would trade against the touch at {}", price, touch)
+            }
+            OrderError::WouldLock { price, quote } => {
+                write!(f, "Order at {} would lock or cross the protected quote at {}", price, quote)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::book::{OrderBook, Side, Trade};
+use crate::error::OrderError;
+
+/// A set of books, one per symbol, sharing one order id sequence so ids are
+/// unique across every instrument.
+#[derive(Debug)]
+pub struct Exchange {
+    books: HashMap<String, OrderBook>,
+    next_order_id: u64,
+}
+
+impl Default for Exchange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Exchange {
+    pub fn new() -> Self {
+        Self {
+            books: HashMap::new(),
+            next_order_id: 1,
+        }
+    }
+
+    /// Listed symbols in alphabetical order.
+    pub fn list_symbols(&self) -> Vec<&str> {
+        let mut symbols: Vec<&str> = self.books.keys().map(String::as_str).collect();
+        symbols.sort_unstable();
+        symbols
+    }
+
+    /// Lists a new symbol with an empty book. Returns false, leaving the
+    /// existing book alone, if it is already listed.
+    pub fn add_symbol(&mut self, symbol: &str) -> bool {
+        if self.books.contains_key(symbol) {
+            return false;
+        }
+        self.books.insert(symbol.to_string(), OrderBook::new(symbol.to_string()));
+        true
+    }
+
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    pub fn book_mut(&mut self, symbol: &str) -> Option<&mut OrderBook> {
+        self.books.get_mut(symbol)
+    }
+
+    /// Routes an order to `symbol`'s book under a fresh exchange-wide id.
+    /// Returns the id with the resulting trades.
+    pub fn place_order(&mut self, symbol: &str, side: Side, price: i64, quantity: i64) -> Result<(u64, Vec<Trade>), OrderError> {
+        let book = self
+            .books
+            .get_mut(symbol)
+            .ok_or_else(|| OrderError::UnknownSymbol(symbol.to_string()))?;
+        let id = self.next_order_id;
+        let trades = book.place_order(side, price, quantity, id)?;
+        self.next_order_id += 1;
+        Ok((id, trades))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exchange_isolates_books() {
+        let mut exchange = Exchange::new();
+        assert!(exchange.add_symbol("Valhalla/USD"));
+        assert!(exchange.add_symbol("Asgard/USD"));
+        assert!(!exchange.add_symbol("Asgard/USD"));
+        assert_eq!(exchange.list_symbols(), vec!["Asgard/USD", "Valhalla/USD"]);
+
+        let (ask_id, _) = exchange.place_order("Valhalla/USD", Side::Sell, 100500, 10000).unwrap();
+        exchange.place_order("Asgard/USD", Side::Sell, 5000, 20000).unwrap();
+
+        // A bid that would cross Valhalla's ask only sees Asgard's book
+        let (bid_id, trades) = exchange.place_order("Asgard/USD", Side::Buy, 100500, 5000).unwrap();
+        assert_ne!(ask_id, bid_id);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].price, 5000);
+        assert_eq!(exchange.book("Valhalla/USD").unwrap().best_sell(), Some((100500, 10000)));
+
+        let (_, trades) = exchange.place_order("Valhalla/USD", Side::Buy, 100500, 10000).unwrap();
+        assert_eq!(trades[0].maker_id, ask_id);
+        assert_eq!(exchange.book("Asgard/USD").unwrap().best_sell(), Some((5000, 15000)));
+
+        assert_eq!(
+            exchange.place_order("Midgard/USD", Side::Buy, 100, 1),
+            Err(OrderError::UnknownSymbol("Midgard/USD".to_string()))
+        );
+    }
+}
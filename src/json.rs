@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book::{Order, OrderBook, Side};
+use crate::persist::{unsupported_version, SCHEMA_VERSION};
+
+/// Serializable view of a book: its resting orders and sequence counters.
+/// Like `checkpoint`, config, session stats and parked orders are left out.
+#[derive(Serialize, Deserialize)]
+struct JsonBook {
+    /// `SCHEMA_VERSION` when written; 1 if missing.
+    #[serde(default = "unversioned")]
+    version: u32,
+    symbol: String,
+    next_timestamp: u64,
+    event_count: u64,
+    /// Bids then asks, each in price order and queue order within a level.
+    orders: Vec<Order>,
+}
+
+fn unversioned() -> u32 {
+    1
+}
+
+impl OrderBook {
+    pub fn to_json(&self) -> String {
+        let state = JsonBook {
+            version: SCHEMA_VERSION,
+            symbol: self.symbol().to_string(),
+            next_timestamp: self.next_timestamp,
+            event_count: self.event_count,
+            orders: [Side::Buy, Side::Sell]
+                .into_iter()
+                .flat_map(|side| self.levels(side).values().flatten().cloned())
+                .collect(),
+        };
+        serde_json::to_string(&state).expect("book state always serializes")
+    }
+
+    /// Rebuilds a book from `to_json` output, with the default config.
+    /// Output of another `SCHEMA_VERSION` is rejected.
+    pub fn from_json(s: &str) -> Result<OrderBook, String> {
+        let state: JsonBook = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        if state.version != SCHEMA_VERSION {
+            return Err(unsupported_version(state.version));
+        }
+        let mut book = OrderBook::new(state.symbol);
+        book.next_timestamp = state.next_timestamp;
+        book.event_count = state.event_count;
+        for order in state.orders {
+            book.restore_order(order);
+        }
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.seed_distribution(3, 100000, 4, 15000.0).unwrap();
+        book.place_order_expiring(Side::Sell, 100500, 10000, 900, 5).unwrap();
+
+        let mut restored = OrderBook::from_json(&book.to_json()).unwrap();
+        assert_eq!(restored.depth_snapshot(10), book.depth_snapshot(10));
+        assert_eq!(restored.buy_levels, book.buy_levels);
+        assert_eq!(restored.sell_levels, book.sell_levels);
+
+        restored.place_order(Side::Buy, 98000, 10000, 901).unwrap();
+        assert_eq!(restored.get_order(901).unwrap().timestamp, book.sequence() + 1);
+
+        assert!(OrderBook::from_json("{").is_err());
+    }
+
+    #[test]
+    fn test_from_json_rejects_other_versions() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        let json = book.to_json();
+        assert!(json.starts_with(&format!("{{\"version\":{},", SCHEMA_VERSION)));
+
+        let old = json.replacen(&format!("\"version\":{},", SCHEMA_VERSION), "", 1);
+        assert_eq!(OrderBook::from_json(&old).unwrap_err(), unsupported_version(1));
+        let newer = json.replacen(&format!("\"version\":{}", SCHEMA_VERSION), "\"version\":99", 1);
+        assert_eq!(OrderBook::from_json(&newer).unwrap_err(), unsupported_version(99));
+    }
+}
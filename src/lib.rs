@@ -0,0 +1,23 @@
+pub mod analytics;
+pub mod book;
+pub mod clock;
+pub mod engine;
+pub mod error;
+pub mod exchange;
+#[cfg(feature = "serde")]
+mod json;
+mod persist;
+pub mod render;
+pub mod replay;
+pub mod script;
+mod synthetic;
+
+pub use book::{BookConfig, BookState, COMPACTED_ASK_ID, COMPACTED_BID_ID, DepthSnapshot, HaltPolicy, Ladder, LadderLevel, MarketFill, MatchingPolicy, OddLotPolicy, Order, OrderBook, OrderFilledCallback, SelfTradePrevention, SessionStats, Side, TickTable, TimeInForce, Trade, TradeCallback, TradingState};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use engine::{AggregatedPrint, Candle, FeeSchedule, TradingEngine};
+pub use error::OrderError;
+pub use exchange::Exchange;
+pub use persist::SCHEMA_VERSION;
+pub use render::{RenderConfig, SpreadDisplay};
+pub use replay::{ReplayOptions, ReplayReport, ReplenishRecord};
+pub use script::{load_script, OrderEvent};
@@ -0,0 +1,189 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::book::{Order, OrderBook, Side};
+
+/// Version of the saved book layout written by `checkpoint` and `to_json`.
+/// Files from before versioning carry no version and count as 1; loading
+/// any version other than this one fails rather than misreading fields.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Error text for a saved book whose layout this build can't read.
+pub(crate) fn unsupported_version(version: u32) -> String {
+    format!("unsupported schema version {} (expected {})", version, SCHEMA_VERSION)
+}
+
+impl OrderBook {
+    /// Writes the resting orders and sequence counters to `path`. The file is
+    /// written next to `path` first and renamed into place, so a crash never
+    /// leaves a partial checkpoint behind. Config, session stats and orders
+    /// parked during a halt are not included.
+    ///
+    /// ```text
+    /// version 3
+    /// symbol Valhalla/USD
+    /// sequence 42
+    /// events 40
+    /// order BUY 99500 10000 1001 7 1700000000000 5 - 0 - 2500
+    /// ```
+    ///
+    /// Order fields are side, price, quantity, id, sequence timestamp, clock
+    /// time, placed event, event lifetime (`-` for none), owner, clock
+    /// expiry (`-` for none) and quantity filled so far, in queue order
+    /// within each level.
+    pub fn checkpoint(&self, path: &str) -> io::Result<()> {
+        let tmp_path = format!("{}.tmp", path);
+        {
+            let file = File::create(&tmp_path)?;
+            let mut out = BufWriter::new(&file);
+            writeln!(out, "version {}", SCHEMA_VERSION)?;
+            writeln!(out, "symbol {}", self.symbol())?;
+            writeln!(out, "sequence {}", self.next_timestamp)?;
+            writeln!(out, "events {}", self.event_count)?;
+            for side in [Side::Buy, Side::Sell] {
+                for orders in self.levels(side).values() {
+                    for o in orders {
+                        let expiry = o.expire_after_events.map_or("-".to_string(), |n| n.to_string());
+                        let expires_at = o.expires_at.map_or("-".to_string(), |t| t.to_string());
+                        writeln!(out, "order {} {} {} {} {} {} {} {} {} {} {}",
+                            o.side, o.price, o.quantity, o.id, o.timestamp, o.created_at, o.placed_event, expiry,
+                            o.owner_id, expires_at, o.filled)?;
+                    }
+                }
+            }
+            out.flush()?;
+            drop(out);
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, path)
+    }
+
+    /// Rebuilds a book from a `checkpoint` file, with the default config.
+    /// Fails with `InvalidData` on a file of another `SCHEMA_VERSION`.
+    pub fn restore(path: &str) -> io::Result<OrderBook> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut book: Option<OrderBook> = None;
+        let mut version = 1;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, msg));
+            let (key, rest) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+
+            if key == "version" && book.is_none() {
+                version = rest.parse().map_err(|_| invalid("invalid version"))?;
+                continue;
+            }
+            if key == "symbol" {
+                if version != SCHEMA_VERSION {
+                    return Err(invalid(&unsupported_version(version)));
+                }
+                book = Some(OrderBook::new(rest.to_string()));
+                continue;
+            }
+            let book = book.as_mut().ok_or_else(|| invalid("expected symbol header"))?;
+            match key {
+                "sequence" => book.next_timestamp = rest.parse().map_err(|_| invalid("invalid sequence"))?,
+                "events" => book.event_count = rest.parse().map_err(|_| invalid("invalid event count"))?,
+                "order" => book.restore_order(parse_order(rest).ok_or_else(|| invalid("invalid order"))?),
+                _ => return Err(invalid("unknown record")),
+            }
+        }
+
+        book.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty checkpoint"))
+    }
+}
+
+fn parse_order(fields: &str) -> Option<Order> {
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let [side, price, quantity, id, timestamp, created_at, placed_event, expiry, owner_id, expires_at, filled] =
+        fields.as_slice()
+    else {
+        return None;
+    };
+    let side = match *side {
+        "BUY" => Side::Buy,
+        "SELL" => Side::Sell,
+        _ => return None,
+    };
+    let expire_after_events = match *expiry {
+        "-" => None,
+        n => Some(n.parse().ok()?),
+    };
+    let expires_at = match *expires_at {
+        "-" => None,
+        t => Some(t.parse().ok()?),
+    };
+    Some(Order {
+        id: id.parse().ok()?,
+        side,
+        price: price.parse().ok()?,
+        quantity: quantity.parse().ok()?,
+        timestamp: timestamp.parse().ok()?,
+        created_at: created_at.parse().ok()?,
+        placed_event: placed_event.parse().ok()?,
+        expire_after_events,
+        expires_at,
+        owner_id: owner_id.parse().ok()?,
+        filled: filled.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_restore_round_trip() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99500, 20000, 2).unwrap();
+        book.place_order(Side::Buy, 99000, 30000, 3).unwrap();
+        book.place_order_expiring(Side::Sell, 100500, 10000, 4, 5).unwrap();
+        book.place_order(Side::Sell, 101000, 40000, 5).unwrap();
+        book.place_order(Side::Buy, 100500, 4000, 6).unwrap();
+
+        let path = std::env::temp_dir().join(format!("lob-checkpoint-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        book.checkpoint(path).unwrap();
+        let mut restored = OrderBook::restore(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(restored.symbol(), "Valhalla/USD");
+        assert_eq!(restored.sequence(), book.sequence());
+        assert_eq!(restored.buy_levels, book.buy_levels);
+        assert_eq!(restored.sell_levels, book.sell_levels);
+
+        // Counters carry on where the original left off
+        restored.place_order(Side::Buy, 98000, 10000, 7).unwrap();
+        assert_eq!(restored.sequence(), book.sequence() + 1);
+        assert_eq!(restored.buy_levels[&98000][0].timestamp, book.sequence() + 1);
+    }
+
+    #[test]
+    fn test_restore_rejects_malformed_file() {
+        let path = std::env::temp_dir().join(format!("lob-checkpoint-bad-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, format!("version {}\nsymbol Valhalla/USD\norder BUY 99500\n", SCHEMA_VERSION)).unwrap();
+        let err = OrderBook::restore(path).unwrap_err();
+        fs::remove_file(path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 3:"), "{}", err);
+    }
+
+    #[test]
+    fn test_restore_rejects_other_versions() {
+        let path = std::env::temp_dir().join(format!("lob-checkpoint-old-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        // Pre-versioning layout: no version line, eight order fields
+        fs::write(path, "symbol Valhalla/USD\nsequence 2\nevents 1\norder BUY 99500 10000 1 1 1 - 0\n").unwrap();
+        let old = OrderBook::restore(path).unwrap_err();
+        fs::write(path, "version 9\nsymbol Valhalla/USD\n").unwrap();
+        let newer = OrderBook::restore(path).unwrap_err();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(old.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(old.to_string(), format!("line 1: unsupported schema version 1 (expected {})", SCHEMA_VERSION));
+        assert_eq!(newer.to_string(), format!("line 2: unsupported schema version 9 (expected {})", SCHEMA_VERSION));
+    }
+}
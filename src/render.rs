@@ -0,0 +1,154 @@
+use crate::book::{LadderLevel, OrderBook};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpreadDisplay {
+    #[default]
+    Dollars,
+    Ticks,
+    Bps,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderConfig {
+    pub spread_display: SpreadDisplay,
+    /// Decimal places for dollar and basis-point spreads.
+    pub spread_decimals: usize,
+    /// Add a column with the quantity summed from the touch out to each
+    /// level.
+    pub show_cumulative: bool,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            spread_display: SpreadDisplay::Dollars,
+            spread_decimals: 2,
+            show_cumulative: false,
+        }
+    }
+}
+
+impl OrderBook {
+    /// The current spread formatted per the book's render config, or "N/A"
+    /// for a one-sided book.
+    pub fn format_spread(&self) -> String {
+        let (Some((ask, _)), Some((bid, _))) = (self.best_sell(), self.best_buy()) else {
+            return "N/A".to_string();
+        };
+        let spread = ask - bid;
+        let render = self.render_config();
+        let decimals = render.spread_decimals;
+
+        match render.spread_display {
+            SpreadDisplay::Dollars => format!("${:.*}", decimals, spread as f64 / 100.0),
+            SpreadDisplay::Ticks => {
+                let tick = self.config().tick_table.tick_for(bid);
+                if spread % tick == 0 {
+                    format!("{} ticks", spread / tick)
+                } else {
+                    format!("{:.*} ticks", decimals, spread as f64 / tick as f64)
+                }
+            }
+            SpreadDisplay::Bps => {
+                let mid = (ask + bid) as f64 / 2.0;
+                format!("{:.*} bps", decimals, spread as f64 / mid * 10_000.0)
+            }
+        }
+    }
+
+    /// The `depth` levels nearest the spread on each side, one line each:
+    /// asks highest first so the best ask sits directly above the spread
+    /// line, then bids best first below it.
+    pub fn ladder_lines(&self, depth: usize) -> Vec<String> {
+        let ladder = self.ladder(depth);
+        let show_cumulative = self.render_config().show_cumulative;
+        let side_lines = |levels: &[LadderLevel]| -> Vec<String> {
+            let mut cumulative = 0i64;
+            levels
+                .iter()
+                .map(|level| {
+                    cumulative = cumulative.saturating_add(level.quantity);
+                    let mut line = format!("${:>7.2} │ {:>8.4} │ {} orders",
+                        level.price as f64 / 100.0, level.quantity as f64 / 10000.0, level.order_count);
+                    if show_cumulative {
+                        line += &format!(" │ Σ {:.4}", cumulative as f64 / 10000.0);
+                    }
+                    line
+                })
+                .collect()
+        };
+
+        let mut lines = vec!["📈 ASK SIDE:".to_string()];
+        lines.extend(side_lines(&ladder.asks).into_iter().rev());
+        lines.push(format!("      ├─ SPREAD: {} ─┤", self.format_spread()));
+        lines.push("📉 BID SIDE:".to_string());
+        lines.extend(side_lines(&ladder.bids));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::{BookConfig, Side, TickTable};
+
+    #[test]
+    fn test_format_spread_modes() {
+        let config = BookConfig { tick_table: TickTable::new(5), ..BookConfig::default() };
+        let mut book = OrderBook::with_config("Valhalla/USD".to_string(), config);
+        assert_eq!(book.format_spread(), "N/A");
+
+        book.place_order(Side::Buy, 99750, 10000, 1).unwrap();
+        book.place_order(Side::Sell, 100250, 10000, 2).unwrap();
+
+        assert_eq!(book.format_spread(), "$5.00");
+
+        book.render_config_mut().spread_decimals = 1;
+        assert_eq!(book.format_spread(), "$5.0");
+
+        book.render_config_mut().spread_display = SpreadDisplay::Ticks;
+        assert_eq!(book.format_spread(), "100 ticks");
+
+        book.render_config_mut().spread_display = SpreadDisplay::Bps;
+        book.render_config_mut().spread_decimals = 2;
+        assert_eq!(book.format_spread(), "50.00 bps");
+    }
+
+    #[test]
+    fn test_ladder_lines_keep_best_asks_by_the_spread() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 4).unwrap();
+        book.place_order(Side::Sell, 101500, 30000, 5).unwrap();
+
+        let lines = book.ladder_lines(2);
+        let spread = lines.iter().position(|l| l.contains("SPREAD")).unwrap();
+        assert!(lines[spread - 1].starts_with("$1005.00"));
+        assert!(lines[spread - 2].starts_with("$1010.00"));
+        assert!(lines.iter().all(|l| !l.contains("1015.00")));
+        assert!(lines[spread + 2].starts_with("$ 995.00"));
+    }
+
+    #[test]
+    fn test_ladder_lines_cumulative_column() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        book.place_order(Side::Buy, 99500, 10000, 1).unwrap();
+        book.place_order(Side::Buy, 99000, 25000, 2).unwrap();
+        book.place_order(Side::Sell, 100500, 10000, 3).unwrap();
+        book.place_order(Side::Sell, 101000, 20000, 4).unwrap();
+        book.place_order(Side::Sell, 101500, 30000, 5).unwrap();
+        assert!(book.ladder_lines(5).iter().all(|l| !l.contains('Σ')));
+
+        book.render_config_mut().show_cumulative = true;
+        let cumulative: Vec<f64> = book
+            .ladder_lines(5)
+            .iter()
+            .filter_map(|l| l.split("Σ ").nth(1))
+            .map(|v| v.parse().unwrap())
+            .collect();
+        // Asks print furthest first, so their running totals read downwards
+        assert_eq!(cumulative, vec![6.0, 3.0, 1.0, 1.0, 3.5]);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read};
+use std::time::{Duration, Instant};
+
+use crate::book::{OrderBook, Side, Trade};
+use crate::error::OrderError;
+
+/// Controls what `OrderBook::replay` measures while it runs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayOptions {
+    /// Time the replay with the wall clock and report throughput.
+    pub measure_throughput: bool,
+    /// Record how each swept level refills; see `ReplenishRecord`.
+    pub track_replenishment: bool,
+    /// Record the quoted spread after each order; see
+    /// `ReplayReport::spread_volatility`.
+    pub track_spread: bool,
+}
+
+/// One sweep of a price level during a replay and what came back after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplenishRecord {
+    pub side: Side,
+    pub price: i64,
+    /// Index of the replayed order that swept the level.
+    pub event: usize,
+    /// Quantity taken from the level by that order.
+    pub swept: i64,
+    /// Quantity rested at the level by later orders, until it is swept again.
+    pub replenished: i64,
+}
+
+/// Outcome of a replay: every trade produced, in order, plus timing when
+/// it was requested.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub trades: Vec<Trade>,
+    pub elapsed: Option<Duration>,
+    pub events_per_second: Option<f64>,
+    /// Filled in when `track_replenishment` is set, in sweep order.
+    pub replenishment: Vec<ReplenishRecord>,
+    /// Spread in cents after each order that left the book two-sided,
+    /// filled in when `track_spread` is set.
+    pub spreads: Vec<i64>,
+}
+
+impl ReplayReport {
+    /// Population standard deviation of `spreads`, in cents. `None` with
+    /// fewer than two samples.
+    pub fn spread_volatility(&self) -> Option<f64> {
+        if self.spreads.len() < 2 {
+            return None;
+        }
+        let n = self.spreads.len() as f64;
+        let mean = self.spreads.iter().sum::<i64>() as f64 / n;
+        let variance = self.spreads.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / n;
+        Some(variance.sqrt())
+    }
+}
+
+impl OrderBook {
+    /// Places each `(side, price, quantity, id)` order in turn, collecting
+    /// the resulting trades. Stops at the first rejected order.
+    pub fn replay(&mut self, orders: &[(Side, i64, i64, u64)], options: ReplayOptions) -> Result<ReplayReport, OrderError> {
+        let started = options.measure_throughput.then(Instant::now);
+        let mut report = ReplayReport::default();
+        // Level -> index of its latest sweep in `report.replenishment`
+        let mut open_sweeps: HashMap<(Side, i64), usize> = HashMap::new();
+        for (event, &(side, price, quantity, id)) in orders.iter().enumerate() {
+            let fills = self.place_order(side, price, quantity, id)?;
+            if options.track_replenishment {
+                if let Some(rested) = self.get_order(id)
+                    && let Some(&index) = open_sweeps.get(&(side, price))
+                {
+                    report.replenishment[index].replenished += rested.quantity;
+                }
+                let mut swept: BTreeMap<i64, i64> = BTreeMap::new();
+                for trade in &fills {
+                    *swept.entry(trade.price).or_default() += trade.quantity;
+                }
+                for (level_price, swept) in swept {
+                    let maker_side = side.opposite();
+                    open_sweeps.insert((maker_side, level_price), report.replenishment.len());
+                    report.replenishment.push(ReplenishRecord {
+                        side: maker_side,
+                        price: level_price,
+                        event,
+                        swept,
+                        replenished: 0,
+                    });
+                }
+            }
+            if options.track_spread
+                && let (Some((bid, _)), Some((ask, _))) = (self.best_buy(), self.best_sell())
+            {
+                report.spreads.push(ask - bid);
+            }
+            report.trades.extend(fills);
+        }
+        if let Some(started) = started {
+            let elapsed = started.elapsed();
+            report.elapsed = Some(elapsed);
+            // A batch can finish inside the clock's resolution; clamp rather than divide by zero
+            let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+            report.events_per_second = Some(orders.len() as f64 / secs);
+        }
+        Ok(report)
+    }
+
+    /// Places each `side,price,quantity,id` row of a CSV recording in turn
+    /// and returns all resulting trades. Prices and quantities are in the
+    /// book's internal units; side is `BUY` or `SELL`. Blank lines and an
+    /// optional `side,price,quantity,id` header are skipped. Malformed or
+    /// rejected rows stop the replay with a line-numbered error.
+    pub fn replay_csv<R: Read>(&mut self, reader: R) -> Result<Vec<Trade>, String> {
+        let mut trades = Vec::new();
+        for (index, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(|e| format!("line {}: {}", index + 1, e))?;
+            let line = line.trim();
+            if line.is_empty() || (index == 0 && line.eq_ignore_ascii_case("side,price,quantity,id")) {
+                continue;
+            }
+            let (side, price, quantity, id) = parse_csv_row(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+            let fills = self
+                .place_order(side, price, quantity, id)
+                .map_err(|e| format!("line {}: {}", index + 1, e))?;
+            trades.extend(fills);
+        }
+        Ok(trades)
+    }
+}
+
+fn parse_csv_row(line: &str) -> Result<(Side, i64, i64, u64), String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [side, price, quantity, id] = fields.as_slice() else {
+        return Err(format!("expected 4 fields, found {}", fields.len()));
+    };
+    let side = match side.to_ascii_uppercase().as_str() {
+        "BUY" => Side::Buy,
+        "SELL" => Side::Sell,
+        _ => return Err(format!("invalid side '{}'", side)),
+    };
+    let price = price.parse().map_err(|_| format!("invalid price '{}'", price))?;
+    let quantity = quantity.parse().map_err(|_| format!("invalid quantity '{}'", quantity))?;
+    let id = id.parse().map_err(|_| format!("invalid id '{}'", id))?;
+    Ok((side, price, quantity, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::DepthSnapshot;
+
+    #[test]
+    fn test_replay_csv() {
+        let csv = b"side,price,quantity,id
+BUY,99500,10000,1
+SELL,100500,10000,2
+sell,100500,5000,3
+
+BUY,100500,12000,4
+" as &[u8];
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let trades = book.replay_csv(csv).unwrap();
+
+        let fills: Vec<(u64, u64, i64)> = trades.iter().map(|t| (t.maker_id, t.taker_id, t.quantity)).collect();
+        assert_eq!(fills, vec![(2, 4, 10000), (3, 4, 2000)]);
+        assert_eq!(book.depth_snapshot(5), DepthSnapshot {
+            symbol: "Valhalla/USD".to_string(),
+            bids: vec![(99500, 10000)],
+            asks: vec![(100500, 3000)],
+            spread: Some(1000),
+        });
+
+        let err = book.replay_csv(b"BUY,99500,10000,5\nBUY,abc,1,6\n" as &[u8]).unwrap_err();
+        assert_eq!(err, "line 2: invalid price 'abc'");
+        assert!(book.replay_csv(b"BUY,99500\n" as &[u8]).unwrap_err().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn test_replay_replenishment() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let orders = [
+            (Side::Sell, 100500, 10000, 1),
+            (Side::Sell, 101000, 10000, 2),
+            (Side::Buy, 101000, 15000, 3),
+            (Side::Sell, 100500, 4000, 4),
+            (Side::Sell, 100500, 3000, 5),
+            (Side::Sell, 101000, 2000, 6),
+            (Side::Buy, 99000, 5000, 7),
+        ];
+        let options = ReplayOptions { track_replenishment: true, ..ReplayOptions::default() };
+        let report = book.replay(&orders, options).unwrap();
+
+        assert_eq!(report.replenishment, vec![
+            ReplenishRecord { side: Side::Sell, price: 100500, event: 2, swept: 10000, replenished: 7000 },
+            ReplenishRecord { side: Side::Sell, price: 101000, event: 2, swept: 5000, replenished: 2000 },
+        ]);
+        assert!(book.replay(&[], ReplayOptions::default()).unwrap().replenishment.is_empty());
+    }
+
+    #[test]
+    fn test_replay_spread_volatility() {
+        let options = ReplayOptions { track_spread: true, ..ReplayOptions::default() };
+        let run = |orders: &[(Side, i64, i64, u64)]| {
+            OrderBook::new("Valhalla/USD".to_string()).replay(orders, options).unwrap()
+        };
+
+        let calm = run(&[
+            (Side::Buy, 99500, 10000, 1),
+            (Side::Sell, 100500, 10000, 2),
+            (Side::Buy, 99600, 10000, 3),
+            (Side::Sell, 100400, 10000, 4),
+        ]);
+        assert_eq!(calm.spreads, vec![1000, 900, 800]);
+        let calm_vol = calm.spread_volatility().unwrap();
+        assert!((calm_vol - (20000.0f64 / 3.0).sqrt()).abs() < 1e-9);
+
+        let wild = run(&[
+            (Side::Buy, 99500, 10000, 1),
+            (Side::Sell, 100500, 10000, 2),
+            (Side::Buy, 99950, 10000, 3),
+            (Side::Sell, 99950, 10000, 4),
+            (Side::Buy, 100500, 10000, 5),
+            (Side::Sell, 104000, 10000, 6),
+        ]);
+        assert!(wild.spread_volatility().unwrap() > calm_vol);
+
+        assert_eq!(run(&[(Side::Buy, 99500, 10000, 1), (Side::Sell, 100500, 10000, 2)]).spread_volatility(), None);
+    }
+
+    #[test]
+    fn test_replay_throughput() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        let orders: Vec<_> = (0..1000u64)
+            .map(|i| {
+                let side = if i % 2 == 0 { Side::Sell } else { Side::Buy };
+                (side, 100000, 10000, i + 1)
+            })
+            .collect();
+
+        let report = book.replay(&orders, ReplayOptions { measure_throughput: true, ..ReplayOptions::default() }).unwrap();
+        assert_eq!(report.trades.len(), 500);
+        assert!(report.elapsed.is_some());
+        assert!(report.events_per_second.unwrap() > 0.0);
+
+        let report = book.replay(&orders[..2], ReplayOptions::default()).unwrap();
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!(report.events_per_second, None);
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::book::Side;
+
+/// One line of an order script. Prices and quantities are in display units
+/// (dollars and Valhalla), exactly as typed at the interactive prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderEvent {
+    Place { side: Side, price: f64, quantity: f64 },
+    Cancel { id: u64 },
+}
+
+/// Loads an order script such as:
+///
+/// ```text
+/// # warm-up
+/// BUY 1000 10
+/// SELL 1005 2.5
+/// CANCEL 1042
+/// ```
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn load_script(path: &str) -> io::Result<Vec<OrderEvent>> {
+    parse_script(BufReader::new(File::open(path)?))
+}
+
+pub fn parse_script<R: BufRead>(reader: R) -> io::Result<Vec<OrderEvent>> {
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let event = parse_line(line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, e)))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+fn parse_line(line: &str) -> Result<OrderEvent, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields.as_slice() {
+        [command, price, quantity] if !command.eq_ignore_ascii_case("cancel") => {
+            let side = match command.to_ascii_uppercase().as_str() {
+                "BUY" => Side::Buy,
+                "SELL" => Side::Sell,
+                _ => return Err(format!("unknown command '{}'", command)),
+            };
+            let price = price.parse().map_err(|_| format!("invalid price '{}'", price))?;
+            let quantity = quantity.parse().map_err(|_| format!("invalid quantity '{}'", quantity))?;
+            Ok(OrderEvent::Place { side, price, quantity })
+        }
+        [command, id] if command.eq_ignore_ascii_case("cancel") => {
+            let id = id.parse().map_err(|_| format!("invalid order id '{}'", id))?;
+            Ok(OrderEvent::Cancel { id })
+        }
+        _ => Err(format!("expected 'BUY|SELL <price> <quantity>' or 'CANCEL <id>', got '{}'", line)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let script = "# warm-up\nBUY 1000 10\n\nsell 1005.5 2.5\nCANCEL 1042\n";
+        let events = parse_script(script.as_bytes()).unwrap();
+        assert_eq!(events, vec![
+            OrderEvent::Place { side: Side::Buy, price: 1000.0, quantity: 10.0 },
+            OrderEvent::Place { side: Side::Sell, price: 1005.5, quantity: 2.5 },
+            OrderEvent::Cancel { id: 1042 },
+        ]);
+
+        let err = parse_script("BUY 1000 10\nBUY 1000 lots\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 2:"), "{}", err);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::book::{OrderBook, Side};
+use crate::error::OrderError;
+
+impl OrderBook {
+    /// Fills `levels` price levels either side of `base_price` with random
+    /// orders, reproducibly for a given `seed`. Levels sit one tick apart,
+    /// starting one tick from `base_price`. Each holds one to three orders,
+    /// and each order's size is drawn from an exponential distribution with
+    /// mean `size_lambda` (in 0.0001 units, at least 1). Order ids count up
+    /// from the book's sequence counter, skipping ids already resting.
+    pub fn seed_distribution(&mut self, seed: u64, base_price: i64, levels: usize, size_lambda: f64) -> Result<(), OrderError> {
+        let mut rng = SplitMix64(seed);
+        let tick = self.config().tick_table.tick_for(base_price);
+        let mut next_id = self.sequence() + 1;
+
+        for level in 1..=levels as i64 {
+            for (side, price) in [(Side::Buy, base_price - level * tick), (Side::Sell, base_price + level * tick)] {
+                for _ in 0..=rng.next_u64() % 3 {
+                    let size = (-rng.next_f64().ln() * size_lambda).round().max(1.0) as i64;
+                    while self.contains(next_id) {
+                        next_id += 1;
+                    }
+                    self.place_order(side, price, size, next_id)?;
+                    next_id += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Small seeded generator (SplitMix64); plenty for synthetic books and
+/// avoids pulling in a dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in (0, 1], so `ln` is always finite.
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_distribution_is_reproducible() {
+        let seeded = |seed| {
+            let mut book = OrderBook::new("Valhalla/USD".to_string());
+            book.seed_distribution(seed, 100000, 5, 20000.0).unwrap();
+            book
+        };
+
+        let (a, b) = (seeded(42), seeded(42));
+        assert_eq!(a.depth_snapshot(10), b.depth_snapshot(10));
+        assert_eq!(a.depth_snapshot(10).bids.len(), 5);
+        assert_eq!(a.best_buy().unwrap().0, 99999);
+        assert_eq!(a.best_sell().unwrap().0, 100001);
+
+        assert_ne!(a.depth_snapshot(10), seeded(7).depth_snapshot(10));
+    }
+
+    #[test]
+    fn test_seed_distribution_skips_resting_ids() {
+        let mut book = OrderBook::new("Valhalla/USD".to_string());
+        // Caller-chosen ids just past the sequence counter
+        book.place_order(Side::Buy, 90000, 10000, 2).unwrap();
+        book.place_order(Side::Sell, 110000, 10000, 3).unwrap();
+
+        book.seed_distribution(42, 100000, 5, 20000.0).unwrap();
+        assert_eq!(book.get_order(2).map(|o| o.price), Some(90000));
+        assert_eq!(book.get_order(3).map(|o| o.price), Some(110000));
+        assert!(book.order_count() >= 12);
+    }
+}